@@ -0,0 +1,278 @@
+//! Directions on a grid where `x` grows to the right and `y` grows down.
+
+use std::{fmt, str::FromStr};
+
+/// One of the four straight directions.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Direction4 {
+  Up,
+  Right,
+  Down,
+  Left,
+}
+
+/// One of the four straight or four diagonal directions.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Direction8 {
+  Up,
+  UpRight,
+  Right,
+  DownRight,
+  Down,
+  DownLeft,
+  Left,
+  UpLeft,
+}
+
+/// A direction that couldn't be parsed.
+#[derive(Debug, PartialEq, Eq)]
+pub struct ParseDirectionError(pub String);
+
+impl fmt::Display for ParseDirectionError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "unknown direction {:?}", self.0)
+  }
+}
+
+impl std::error::Error for ParseDirectionError {}
+
+impl Direction4 {
+  /// All directions, clockwise from up.
+  pub const ALL: [Direction4; 4] = [
+    Direction4::Up,
+    Direction4::Right,
+    Direction4::Down,
+    Direction4::Left,
+  ];
+
+  pub fn iter() -> impl Iterator<Item = Self> { Self::ALL.into_iter() }
+
+  /// Turns clockwise by the given number of quarter turns, or anticlockwise
+  /// if it is negative.
+  pub fn rotate(&self, quarters: isize) -> Self {
+    Self::ALL[(self.as_usize() as isize + quarters).rem_euclid(4) as usize]
+  }
+
+  pub fn turn_right(&self) -> Self { self.rotate(1) }
+  pub fn turn_left(&self) -> Self { self.rotate(-1) }
+  pub fn turn_around(&self) -> Self { self.rotate(2) }
+
+  /// The direction pointing the other way, the same as
+  /// [`Direction4::turn_around`].
+  pub fn opposite(&self) -> Self { self.turn_around() }
+
+  /// The change in `x` and `y` from taking one step this way.
+  pub fn vector(&self) -> (isize, isize) { Direction8::from(*self).vector() }
+
+  /// The arrow that points this way, as in `^`.
+  pub fn glyph(&self) -> char {
+    match self {
+      Direction4::Up => '^',
+      Direction4::Right => '>',
+      Direction4::Down => 'v',
+      Direction4::Left => '<',
+    }
+  }
+
+  /// The direction an arrow glyph points, if `c` is one.
+  pub fn from_glyph(c: char) -> Option<Self> {
+    Self::iter().find(|d| d.glyph() == c)
+  }
+
+  /// A unique index for the direction, from 0 to 3, for tables with an
+  /// entry per direction.
+  pub fn as_usize(&self) -> usize {
+    match self {
+      Direction4::Up => 0,
+      Direction4::Right => 1,
+      Direction4::Down => 2,
+      Direction4::Left => 3,
+    }
+  }
+}
+
+impl Direction8 {
+  /// All directions, clockwise from up.
+  pub const ALL: [Direction8; 8] = [
+    Direction8::Up,
+    Direction8::UpRight,
+    Direction8::Right,
+    Direction8::DownRight,
+    Direction8::Down,
+    Direction8::DownLeft,
+    Direction8::Left,
+    Direction8::UpLeft,
+  ];
+
+  pub fn iter() -> impl Iterator<Item = Self> { Self::ALL.into_iter() }
+
+  /// Turns clockwise by the given number of eighths of a full turn, or
+  /// anticlockwise if it is negative.
+  pub fn rotate(&self, eighths: isize) -> Self {
+    let index = Self::ALL.iter().position(|d| d == self).unwrap() as isize;
+    Self::ALL[(index + eighths).rem_euclid(8) as usize]
+  }
+
+  /// Turns a quarter turn clockwise, as [`Direction4::turn_right`] does.
+  pub fn turn_right(&self) -> Self { self.rotate(2) }
+  /// Turns a quarter turn anticlockwise, as [`Direction4::turn_left`] does.
+  pub fn turn_left(&self) -> Self { self.rotate(-2) }
+  pub fn turn_around(&self) -> Self { self.rotate(4) }
+
+  /// The direction pointing the other way, the same as
+  /// [`Direction8::turn_around`].
+  pub fn opposite(&self) -> Self { self.turn_around() }
+
+  pub fn is_diagonal(&self) -> bool { Direction4::try_from(*self).is_err() }
+
+  /// The change in `x` and `y` from taking one step this way.
+  pub fn vector(&self) -> (isize, isize) {
+    match self {
+      Direction8::Up => (0, -1),
+      Direction8::UpRight => (1, -1),
+      Direction8::Right => (1, 0),
+      Direction8::DownRight => (1, 1),
+      Direction8::Down => (0, 1),
+      Direction8::DownLeft => (-1, 1),
+      Direction8::Left => (-1, 0),
+      Direction8::UpLeft => (-1, -1),
+    }
+  }
+
+  /// The arrow that points this way, or a slash along the diagonals.
+  pub fn glyph(&self) -> char {
+    match Direction4::try_from(*self) {
+      Ok(dir) => dir.glyph(),
+      Err(()) => match self {
+        Direction8::UpRight | Direction8::DownLeft => '/',
+        _ => '\\',
+      },
+    }
+  }
+
+  /// A unique index for the direction, from 0 to 7. The straight directions
+  /// come first, with the same indices as in [`Direction4::as_usize`], so
+  /// tables that only deal with those can have four entries.
+  pub fn as_usize(&self) -> usize {
+    match self {
+      Direction8::Up => 0,
+      Direction8::Right => 1,
+      Direction8::Down => 2,
+      Direction8::Left => 3,
+      Direction8::UpRight => 4,
+      Direction8::DownRight => 5,
+      Direction8::DownLeft => 6,
+      Direction8::UpLeft => 7,
+    }
+  }
+}
+
+impl From<Direction4> for Direction8 {
+  fn from(dir: Direction4) -> Self {
+    match dir {
+      Direction4::Up => Direction8::Up,
+      Direction4::Right => Direction8::Right,
+      Direction4::Down => Direction8::Down,
+      Direction4::Left => Direction8::Left,
+    }
+  }
+}
+
+impl TryFrom<Direction8> for Direction4 {
+  /// The direction is diagonal.
+  type Error = ();
+
+  fn try_from(dir: Direction8) -> Result<Self, Self::Error> {
+    match dir {
+      Direction8::Up => Ok(Direction4::Up),
+      Direction8::Right => Ok(Direction4::Right),
+      Direction8::Down => Ok(Direction4::Down),
+      Direction8::Left => Ok(Direction4::Left),
+      _ => Err(()),
+    }
+  }
+}
+
+/// Parses an arrow (`^ > v <`), a letter (`U R D L`) or a compass point
+/// (`N E S W`).
+impl FromStr for Direction4 {
+  type Err = ParseDirectionError;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "^" | "U" | "N" => Ok(Direction4::Up),
+      ">" | "R" | "E" => Ok(Direction4::Right),
+      "v" | "D" | "S" => Ok(Direction4::Down),
+      "<" | "L" | "W" => Ok(Direction4::Left),
+      s => Err(ParseDirectionError(s.to_string())),
+    }
+  }
+}
+
+/// Parses anything [`Direction4`] does, and the diagonals as pairs of letters
+/// (`UR`) or compass points (`NE`).
+impl FromStr for Direction8 {
+  type Err = ParseDirectionError;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "UR" | "NE" => Ok(Direction8::UpRight),
+      "DR" | "SE" => Ok(Direction8::DownRight),
+      "DL" | "SW" => Ok(Direction8::DownLeft),
+      "UL" | "NW" => Ok(Direction8::UpLeft),
+      s => s.parse::<Direction4>().map(Direction8::from),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn turns() {
+    for dir in Direction4::iter() {
+      assert_eq!(dir.turn_right().turn_left(), dir);
+      assert_eq!(dir.turn_right().turn_right(), dir.opposite());
+      assert_eq!(
+        Direction8::from(dir).turn_right(),
+        Direction8::from(dir.turn_right())
+      );
+      assert_eq!(Direction8::from(dir).turn_around(), dir.opposite().into());
+    }
+    for dir in Direction8::iter() {
+      let (dx, dy) = dir.vector();
+      assert_eq!(dir.opposite().vector(), (-dx, -dy));
+      assert_eq!(dir.rotate(1).rotate(-1), dir);
+    }
+    assert_eq!(Direction4::Left.turn_right(), Direction4::Up);
+    assert_eq!(Direction8::UpLeft.rotate(1), Direction8::Up);
+  }
+
+  #[test]
+  fn parsing() {
+    for (s, dir) in [("^", Direction4::Up), ("E", Direction4::Right)] {
+      assert_eq!(s.parse(), Ok(dir));
+    }
+    assert_eq!(Direction4::from_glyph('v'), Some(Direction4::Down));
+    assert_eq!(Direction4::from_glyph('D'), None);
+    assert_eq!("NW".parse(), Ok(Direction8::UpLeft));
+    assert_eq!("<".parse(), Ok(Direction8::Left));
+    assert_eq!(
+      "NW".parse::<Direction4>(),
+      Err(ParseDirectionError("NW".to_string()))
+    );
+  }
+
+  #[test]
+  fn indices() {
+    for dir in Direction4::iter() {
+      assert_eq!(Direction8::from(dir).as_usize(), dir.as_usize());
+    }
+    let mut indices: Vec<_> =
+      Direction8::iter().map(|d| d.as_usize()).collect();
+    indices.sort();
+    assert_eq!(indices, (0..8).collect::<Vec<_>>());
+    assert_eq!(Direction8::iter().filter(|d| d.is_diagonal()).count(), 4);
+  }
+}
@@ -76,16 +76,14 @@ impl Input {
 
     let intersection = first_keyset.intersection(&second_keyset);
 
-    let output = intersection
+    intersection
       .map(|key| {
         let first_count = first_histogram.get(key).unwrap();
         let second_count = second_histogram.get(key).unwrap();
 
         **key * (first_count * second_count)
       })
-      .sum::<u32>();
-
-    output
+      .sum::<u32>()
   }
 }
 
@@ -1,6 +1,8 @@
+mod scan;
+
 use std::path::{Path, PathBuf};
 
-use regex::Regex;
+use self::scan::Scanner;
 
 #[derive(Debug, Clone)]
 struct Input {
@@ -11,78 +13,26 @@ impl Input {
   fn from_file(input_file_path: &Path) -> Self {
     let data = std::fs::read_to_string(input_file_path)
       .expect("failed to read input file");
-    Self { data }
+    Self::from_string(data)
   }
 
-  fn part_1(&self) -> u64 {
-    let re =
-      Regex::new(r"mul\(([0-9]+),([0-9]+)\)").expect("failed to build regex");
+  fn from_string(data: String) -> Self { Self { data } }
 
-    let output = re
-      .captures_iter(&self.data)
-      .map(|cap| {
-        let first =
-          cap[1].parse::<u64>().expect("failed to parse first number");
-        let second = cap[2]
-          .parse::<u64>()
-          .expect("failed to parse second number");
-
-        first * second
+  fn part_1(&self) -> u64 {
+    Scanner::new(&self.data)
+      .map(|(_, instruction)| match instruction {
+        Instruction::Mul(first, second) => first * second,
+        Instruction::Do | Instruction::Dont => 0,
       })
-      .sum::<u64>();
-
-    output
+      .sum()
   }
 
   fn part_2(&self) -> u64 {
-    // parse mul instructions, and keep their position in the text
-    let mul_re =
-      Regex::new(r"mul\(([0-9]+),([0-9]+)\)").expect("failed to build regex");
-    let mul_instructions: Vec<_> = mul_re
-      .captures_iter(&self.data)
-      .map(|cap| {
-        let first = cap
-          .get(1)
-          .unwrap()
-          .as_str()
-          .parse::<u64>()
-          .expect("failed to parse first number");
-        let second = cap
-          .get(2)
-          .unwrap()
-          .as_str()
-          .parse::<u64>()
-          .expect("failed to parse second number");
-
-        (cap.get(0).unwrap().start(), Instruction::Mul(first, second))
-      })
-      .collect();
-
-    // parse do instructions, and keep their position in the text
-    let do_re = Regex::new(r"do\(\)").expect("failed to build regex");
-    let do_instructions: Vec<_> = do_re
-      .find_iter(&self.data)
-      .map(|m| (m.start(), Instruction::Do))
-      .collect();
-
-    // parse dont instructions, and keep their position in the text
-    let dont_re = Regex::new(r"don't\(\)").expect("failed to build regex");
-    let dont_instructions: Vec<_> = dont_re
-      .find_iter(&self.data)
-      .map(|m| (m.start(), Instruction::Dont))
-      .collect();
-
-    // merge all instructions, sort them by their position in the text
-    let mut instructions = mul_instructions;
-    instructions.extend(do_instructions);
-    instructions.extend(dont_instructions);
-    instructions.sort_by_key(|(s, _)| *s);
-
     // apply instructions in order. `mul` is disabled after `dont`, and enabled
     // after `do`
     let mut mul_enabled = true;
     let mut output = 0;
-    for (_, instruction) in instructions {
+    for (_, instruction) in Scanner::new(&self.data) {
       match instruction {
         Instruction::Mul(first, second) => {
           if mul_enabled {
@@ -102,7 +52,8 @@ impl Input {
   }
 }
 
-enum Instruction {
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Instruction {
   Mul(u64, u64),
   Do,
   Dont,
@@ -124,3 +75,128 @@ fn main() {
     now.elapsed().as_secs_f32() * 1000.0
   );
 }
+
+#[cfg(test)]
+mod tests {
+  use rand::{Rng, SeedableRng, rngs::StdRng, seq::SliceRandom};
+  use regex::Regex;
+
+  use super::*;
+
+  /// The instructions as the regex solution found them: one regex per
+  /// instruction, merged by offset.
+  fn regex_instructions(data: &str) -> Vec<(usize, Instruction)> {
+    let mul_re =
+      Regex::new(r"mul\(([0-9]+),([0-9]+)\)").expect("failed to build regex");
+    let mut instructions: Vec<_> = mul_re
+      .captures_iter(data)
+      .map(|cap| {
+        let first = cap[1].parse().expect("failed to parse first number");
+        let second = cap[2].parse().expect("failed to parse second number");
+        (cap.get(0).unwrap().start(), Instruction::Mul(first, second))
+      })
+      .collect();
+
+    let do_re = Regex::new(r"do\(\)").expect("failed to build regex");
+    instructions
+      .extend(do_re.find_iter(data).map(|m| (m.start(), Instruction::Do)));
+    let dont_re = Regex::new(r"don't\(\)").expect("failed to build regex");
+    instructions.extend(
+      dont_re
+        .find_iter(data)
+        .map(|m| (m.start(), Instruction::Dont)),
+    );
+
+    instructions.sort_by_key(|(s, _)| *s);
+    instructions
+  }
+
+  #[test]
+  fn part_1_example() {
+    let input = Input::from_string(
+      "xmul(2,4)%&mul[3,7]!@^do_not_mul(5,5)+mul(32,64]then(mul(11,8)mul(8,5))"
+        .to_string(),
+    );
+    assert_eq!(input.part_1(), 161);
+  }
+
+  #[test]
+  fn part_2_example() {
+    let input = Input::from_string(
+      "xmul(2,4)&mul[3,7]!^don't()_mul(5,5)+mul(32,64](mul(11,8)undo()?mul(8,\
+       5))"
+        .to_string(),
+    );
+    assert_eq!(input.part_2(), 48);
+  }
+
+  #[test]
+  fn long_operands() {
+    // any number of digits is fine, as long as the number fits in a u64
+    let input = Input::from_string(
+      "mul(99999999999999999999999,2)mul(1234,5)mul(10000000000000000000,1)"
+        .to_string(),
+    );
+    assert_eq!(Scanner::new(&input.data).collect::<Vec<_>>(), vec![
+      (30, Instruction::Mul(1234, 5)),
+      (41, Instruction::Mul(10_000_000_000_000_000_000, 1)),
+    ]);
+    assert_eq!(input.part_1(), 1234 * 5 + 10_000_000_000_000_000_000);
+  }
+
+  #[test]
+  fn scanner_matches_regexes() {
+    // fragments that make near misses as likely as matches
+    const FRAGMENTS: &[&str] = &[
+      "mul(", "mul", "mu", "m", "do()", "don't()", "don't", "do(", "do", "d",
+      "n't", "(", ")", ",", "1", "23", "456", "7890", "x", " ", "'",
+      "mul(3,4)", "\u{e9}",
+    ];
+
+    let mut rng = StdRng::seed_from_u64(3);
+    for _ in 0..2000 {
+      let len = rng.gen_range(0..40);
+      let data: String = (0..len)
+        .map(|_| *FRAGMENTS.choose(&mut rng).unwrap())
+        .collect();
+      // the regexes can't parse numbers that overflow, and the products of
+      // long ones overflow too, so keep to runs of at most nine digits
+      let longest_run = data
+        .split(|c: char| !c.is_ascii_digit())
+        .map(str::len)
+        .max()
+        .unwrap_or(0);
+      if longest_run > 9 {
+        continue;
+      }
+
+      let input = Input::from_string(data.clone());
+      let expected = regex_instructions(&data);
+      assert_eq!(
+        Scanner::new(&data).collect::<Vec<_>>(),
+        expected,
+        "{data:?}"
+      );
+
+      let product = |instruction: &Instruction| match instruction {
+        Instruction::Mul(first, second) => first * second,
+        _ => 0,
+      };
+      assert_eq!(
+        input.part_1(),
+        expected.iter().map(|(_, i)| product(i)).sum::<u64>()
+      );
+      let mut enabled = true;
+      let mut part_2 = 0;
+      for (_, instruction) in &expected {
+        match instruction {
+          Instruction::Do => enabled = true,
+          Instruction::Dont => enabled = false,
+          mul if enabled => part_2 += product(mul),
+          _ => (),
+        }
+      }
+      assert_eq!(input.part_2(), part_2, "{data:?}");
+    }
+  }
+}
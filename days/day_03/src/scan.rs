@@ -0,0 +1,99 @@
+use crate::Instruction;
+
+/// Finds the instructions in corrupted memory in a single pass, yielding
+/// each with its offset in the text.
+///
+/// A failed match stops at the first byte that doesn't fit, and scanning
+/// carries on from that byte, so no byte is looked at more than twice.
+pub struct Scanner<'a> {
+  data: &'a [u8],
+  pos:  usize,
+}
+
+impl<'a> Scanner<'a> {
+  pub fn new(data: &'a str) -> Self {
+    Self {
+      data: data.as_bytes(),
+      pos:  0,
+    }
+  }
+
+  /// Matches `literal` at `at`, returning the offset just past it, or the
+  /// offset of the first byte that differs.
+  fn literal(&self, at: usize, literal: &[u8]) -> Result<usize, usize> {
+    for (i, &expected) in literal.iter().enumerate() {
+      if self.data.get(at + i) != Some(&expected) {
+        return Err(at + i);
+      }
+    }
+    Ok(at + literal.len())
+  }
+
+  /// Matches one or more digits at `at`, returning their value and the
+  /// offset just past them. Digits too many to fit in a `u64` fail the match,
+  /// at the end of the run, since none of them can start an instruction.
+  fn number(&self, at: usize) -> Result<(u64, usize), usize> {
+    let digits = self.data[at..]
+      .iter()
+      .take_while(|c| c.is_ascii_digit())
+      .count();
+    if digits == 0 {
+      return Err(at);
+    }
+
+    let value = self.data[at..at + digits]
+      .iter()
+      .try_fold(0u64, |value, c| {
+        value
+          .checked_mul(10)
+          .and_then(|value| value.checked_add(u64::from(c - b'0')))
+      });
+    match value {
+      Some(value) => Ok((value, at + digits)),
+      None => Err(at + digits),
+    }
+  }
+
+  fn mul(&self, at: usize) -> Result<(Instruction, usize), usize> {
+    let at = self.literal(at, b"mul(")?;
+    let (first, at) = self.number(at)?;
+    let at = self.literal(at, b",")?;
+    let (second, at) = self.number(at)?;
+    let at = self.literal(at, b")")?;
+    Ok((Instruction::Mul(first, second), at))
+  }
+
+  fn do_or_dont(&self, at: usize) -> Result<(Instruction, usize), usize> {
+    let at = self.literal(at, b"do")?;
+    match self.literal(at, b"()") {
+      Ok(at) => Ok((Instruction::Do, at)),
+      Err(_) => Ok((Instruction::Dont, self.literal(at, b"n't()")?)),
+    }
+  }
+}
+
+impl Iterator for Scanner<'_> {
+  type Item = (usize, Instruction);
+
+  fn next(&mut self) -> Option<Self::Item> {
+    while self.pos < self.data.len() {
+      let start = self.pos;
+      let matched = match self.data[start] {
+        b'm' => self.mul(start),
+        b'd' => self.do_or_dont(start),
+        _ => Err(start + 1),
+      };
+
+      match matched {
+        Ok((instruction, end)) => {
+          self.pos = end;
+          return Some((start, instruction));
+        }
+        // the byte that broke the match may start the next one, but the
+        // bytes before it can't
+        Err(failed) => self.pos = failed.max(start + 1),
+      }
+    }
+    None
+  }
+}
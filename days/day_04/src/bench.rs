@@ -0,0 +1,75 @@
+use direction::Direction8;
+
+/// The grid as it was stored before [`crate::grid::Grid`]: a `Vec` of
+/// `char`s per line. Kept to measure the byte grid against.
+pub struct CharGrid {
+  width:   usize,
+  height:  usize,
+  content: Vec<Vec<char>>,
+}
+
+impl CharGrid {
+  pub fn parse(content: &str) -> Self {
+    let content: Vec<Vec<char>> = content
+      .lines()
+      .filter(|line| !line.is_empty())
+      .map(|line| line.chars().collect())
+      .collect();
+
+    Self {
+      width: content.iter().map(Vec::len).max().unwrap_or(0),
+      height: content.len(),
+      content,
+    }
+  }
+
+  fn char_at(&self, x: i32, y: i32) -> Option<char> {
+    if x < 0 || y < 0 {
+      return None;
+    }
+    self.content.get(y as usize)?.get(x as usize).copied()
+  }
+
+  fn cells(&self) -> impl Iterator<Item = (i32, i32)> + '_ {
+    (0..self.height as i32)
+      .flat_map(|y| (0..self.width as i32).map(move |x| (x, y)))
+  }
+
+  pub fn part_1(&self) -> u32 {
+    const MATCH_STRING: [char; 4] = ['X', 'M', 'A', 'S'];
+
+    self
+      .cells()
+      .filter(|&(x, y)| self.char_at(x, y) == Some('X'))
+      .map(|(x, y)| {
+        Direction8::iter()
+          .filter(|dir| {
+            let (dx, dy) = dir.vector();
+            (1..MATCH_STRING.len()).all(|i| {
+              let i = i as i32;
+              self.char_at(x + i * dx as i32, y + i * dy as i32)
+                == Some(MATCH_STRING[i as usize])
+            })
+          })
+          .count() as u32
+      })
+      .sum()
+  }
+
+  pub fn part_2(&self) -> u32 {
+    self
+      .cells()
+      .filter(|&(x, y)| self.char_at(x, y) == Some('A'))
+      .filter(|&(x, y)| {
+        let diagonal = |a: (i32, i32), b: (i32, i32)| {
+          matches!(
+            (self.char_at(a.0, a.1), self.char_at(b.0, b.1)),
+            (Some('M'), Some('S')) | (Some('S'), Some('M'))
+          )
+        };
+        diagonal((x - 1, y - 1), (x + 1, y + 1))
+          && diagonal((x + 1, y - 1), (x - 1, y + 1))
+      })
+      .count() as u32
+  }
+}
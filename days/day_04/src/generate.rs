@@ -0,0 +1,176 @@
+use std::fmt;
+
+use direction::Direction8;
+use rand::{Rng, SeedableRng, rngs::StdRng, seq::SliceRandom};
+
+/// How many random positions to try for each word before giving up on it.
+const PLACEMENT_ATTEMPTS: usize = 1000;
+
+pub struct Config {
+  pub width:       usize,
+  pub height:      usize,
+  pub seed:        u64,
+  /// Words to hide in the grid, in any of the 8 directions.
+  pub words:       Vec<String>,
+  /// Letters used to fill the cells not covered by a word.
+  pub distractors: Vec<u8>,
+}
+
+/// A generated word search, along with its expected answers.
+pub struct Puzzle {
+  pub text:   String,
+  /// How many words were placed. A placement that would only retrace
+  /// letters already in the grid is skipped, so each placed word adds at
+  /// least one new letter.
+  pub placed: usize,
+  pub part_1: u32,
+  pub part_2: u32,
+}
+
+/// Why a puzzle can't be generated from a [`Config`].
+#[derive(Debug, PartialEq, Eq)]
+pub enum GenerateError {
+  /// The width or height is zero.
+  EmptyGrid,
+  NoDistractors,
+}
+
+impl fmt::Display for GenerateError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      GenerateError::EmptyGrid => {
+        write!(f, "width and height must both be at least 1")
+      }
+      GenerateError::NoDistractors => write!(f, "no distractor letters given"),
+    }
+  }
+}
+
+impl std::error::Error for GenerateError {}
+
+impl Config {
+  pub fn generate(&self) -> Result<Puzzle, GenerateError> {
+    if self.width == 0 || self.height == 0 {
+      return Err(GenerateError::EmptyGrid);
+    }
+    if self.distractors.is_empty() {
+      return Err(GenerateError::NoDistractors);
+    }
+
+    let mut rng = StdRng::seed_from_u64(self.seed);
+    let mut cells: Vec<Option<u8>> = vec![None; self.width * self.height];
+
+    // place each word at a random position and direction. a word may cross
+    // other words, as long as the letters it shares with them agree.
+    let mut placed = 0;
+    for word in &self.words {
+      let word = word.as_bytes();
+      for _ in 0..PLACEMENT_ATTEMPTS {
+        let x = rng.gen_range(0..self.width) as i32;
+        let y = rng.gen_range(0..self.height) as i32;
+        let dir = Direction8::ALL[rng.gen_range(0..Direction8::ALL.len())];
+        let (dx, dy) = dir.vector();
+        let (dx, dy) = (dx as i32, dy as i32);
+
+        let positions: Option<Vec<_>> = (0..word.len() as i32)
+          .map(|i| self.index(x + i * dx, y + i * dy))
+          .collect();
+        let Some(positions) = positions else {
+          continue;
+        };
+        let fits = positions
+          .iter()
+          .zip(word)
+          .all(|(&i, &c)| cells[i].is_none_or(|existing| existing == c));
+        // a word that fits without any new letter is already in the grid
+        let adds_letter = positions.iter().any(|&i| cells[i].is_none());
+        if !fits || !adds_letter {
+          continue;
+        }
+
+        for (&i, &c) in positions.iter().zip(word) {
+          cells[i] = Some(c);
+        }
+        placed += 1;
+        break;
+      }
+    }
+
+    let grid: Vec<u8> = cells
+      .into_iter()
+      .map(|c| c.unwrap_or_else(|| *self.distractors.choose(&mut rng).unwrap()))
+      .collect();
+
+    let mut text = String::with_capacity((self.width + 1) * self.height);
+    for row in grid.chunks(self.width) {
+      text.push_str(std::str::from_utf8(row).expect("grid is not utf-8"));
+      text.push('\n');
+    }
+
+    // distractors can form matches of their own, so the answers are counted
+    // on the finished grid rather than from the placed words
+    Ok(Puzzle {
+      part_1: count_xmas(&grid, self.width, self.height),
+      part_2: count_x_mas(&grid, self.width, self.height),
+      text,
+      placed,
+    })
+  }
+
+  fn index(&self, x: i32, y: i32) -> Option<usize> {
+    if x < 0 || y < 0 || x as usize >= self.width || y as usize >= self.height {
+      return None;
+    }
+    Some(y as usize * self.width + x as usize)
+  }
+}
+
+/// Counts `XMAS` by reading every row, column and diagonal as a line, in both
+/// directions. This is deliberately independent of `Input::part_1`.
+fn count_xmas(grid: &[u8], width: usize, height: usize) -> u32 {
+  let at = |x: usize, y: usize| grid[y * width + x];
+
+  let mut lines: Vec<Vec<u8>> = Vec::new();
+  lines.extend((0..height).map(|y| (0..width).map(|x| at(x, y)).collect()));
+  lines.extend((0..width).map(|x| (0..height).map(|y| at(x, y)).collect()));
+  // diagonals going down-right start on the top row or the left column, and
+  // diagonals going down-left start on the top row or the right column
+  let starts = (0..width)
+    .map(|x| (x, 0))
+    .chain((1..height).map(|y| (0, y)));
+  lines.extend(starts.map(|(x, y)| {
+    (0..)
+      .map_while(|i| {
+        (x + i < width && y + i < height).then(|| at(x + i, y + i))
+      })
+      .collect()
+  }));
+  let starts = (0..width)
+    .map(|x| (x, 0))
+    .chain((1..height).map(|y| (width - 1, y)));
+  lines.extend(starts.map(|(x, y)| {
+    (0..)
+      .map_while(|i| (i <= x && y + i < height).then(|| at(x - i, y + i)))
+      .collect()
+  }));
+
+  lines
+    .iter()
+    .flat_map(|line| line.windows(4))
+    .filter(|window| *window == b"XMAS" || *window == b"SAMX")
+    .count() as u32
+}
+
+/// Counts X-MAS crosses by checking both diagonals of every 3x3 window.
+fn count_x_mas(grid: &[u8], width: usize, height: usize) -> u32 {
+  let at = |x: usize, y: usize| grid[y * width + x];
+  let is_mas = |d: [u8; 3]| &d == b"MAS" || &d == b"SAM";
+
+  (0..height.saturating_sub(2))
+    .flat_map(|y| (0..width.saturating_sub(2)).map(move |x| (x, y)))
+    .filter(|&(x, y)| {
+      is_mas([at(x, y), at(x + 1, y + 1), at(x + 2, y + 2)])
+        && is_mas([at(x + 2, y), at(x + 1, y + 1), at(x, y + 2)])
+    })
+    .count() as u32
+}
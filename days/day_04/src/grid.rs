@@ -0,0 +1,109 @@
+use std::fmt;
+
+/// What to do with rows that are shorter than the widest row.
+#[derive(Clone, Copy, Debug)]
+pub enum RaggedRows {
+  /// Pad short rows on the right with the given byte.
+  Pad(u8),
+  /// Refuse to build the grid.
+  Reject,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum GridError {
+  Empty,
+  RaggedRow {
+    line:     usize,
+    len:      usize,
+    expected: usize,
+  },
+}
+
+impl fmt::Display for GridError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      GridError::Empty => write!(f, "grid has no rows"),
+      GridError::RaggedRow {
+        line,
+        len,
+        expected,
+      } => write!(
+        f,
+        "row on line {line} has length {len}, expected {expected}"
+      ),
+    }
+  }
+}
+
+impl std::error::Error for GridError {}
+
+/// A rectangular grid of bytes, stored row-major in one allocation.
+#[derive(Clone, Debug)]
+pub struct Grid {
+  width:  usize,
+  height: usize,
+  cells:  Vec<u8>,
+}
+
+impl Grid {
+  /// Parses a grid from text. Blank lines are skipped, and trailing
+  /// whitespace (including the `\r` of `\r\n` line endings) is stripped from
+  /// every row before its width is measured.
+  pub fn parse(content: &str, ragged: RaggedRows) -> Result<Self, GridError> {
+    // keep the 1-based line number of each row for error reporting
+    let rows: Vec<_> = content
+      .lines()
+      .enumerate()
+      .map(|(i, line)| (i + 1, line.trim_end().as_bytes()))
+      .filter(|(_, line)| !line.is_empty())
+      .collect();
+
+    let width = rows
+      .iter()
+      .map(|(_, row)| row.len())
+      .max()
+      .ok_or(GridError::Empty)?;
+    let height = rows.len();
+
+    let mut cells = Vec::with_capacity(width * height);
+    for (line, row) in rows {
+      if row.len() < width {
+        match ragged {
+          RaggedRows::Pad(_) => (),
+          RaggedRows::Reject => {
+            return Err(GridError::RaggedRow {
+              line,
+              len: row.len(),
+              expected: width,
+            });
+          }
+        }
+      }
+
+      cells.extend_from_slice(row);
+      if let RaggedRows::Pad(fill) = ragged {
+        cells.resize(cells.len() + width - row.len(), fill);
+      }
+    }
+
+    Ok(Self {
+      width,
+      height,
+      cells,
+    })
+  }
+
+  pub fn width(&self) -> usize { self.width }
+  pub fn height(&self) -> usize { self.height }
+
+  pub fn get(&self, x: usize, y: usize) -> u8 { self.cells[y * self.width + x] }
+
+  /// Returns the byte at a signed position, or `None` if it lies outside the
+  /// grid.
+  pub fn get_signed(&self, x: i32, y: i32) -> Option<u8> {
+    if x < 0 || y < 0 || x as usize >= self.width || y as usize >= self.height {
+      return None;
+    }
+    Some(self.get(x as usize, y as usize))
+  }
+}
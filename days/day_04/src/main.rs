@@ -1,146 +1,236 @@
+mod bench;
+mod generate;
+mod grid;
+mod show;
+
 use std::path::{Path, PathBuf};
 
-use rayon::prelude::*;
+use direction::Direction8;
 
-const DIRECTIONS: [(i32, i32); 8] = [
-  (0, 1),
-  (1, 0),
-  (1, 1),
-  (-1, 1),
-  (1, -1),
-  (-1, -1),
-  (0, -1),
-  (-1, 0),
-];
+use self::{
+  grid::{Grid, RaggedRows},
+  show::Style,
+};
 
 #[derive(Debug)]
 struct Input {
-  width:   usize,
-  height:  usize,
-  content: Vec<Vec<char>>,
+  grid: Grid,
 }
 
 impl Input {
-  fn from_file(input_file_path: &Path) -> Self {
+  fn from_file(input_file_path: &Path, ragged: RaggedRows) -> Self {
     let content = std::fs::read_to_string(input_file_path)
       .expect("failed to read input file");
 
-    Self::from_string(content)
+    Self::from_string(content, ragged)
   }
 
-  fn from_string(content: String) -> Self {
-    let content: Vec<_> = content
-      .lines()
-      .filter(|line| !line.is_empty())
-      .map(|line| line.chars().collect())
-      .collect();
-
-    let width = content
-      .iter()
-      .map(Vec::len)
-      .max()
-      .expect("failed to find max line width");
-    let height = content.len();
+  fn from_string(content: String, ragged: RaggedRows) -> Self {
+    let grid = Grid::parse(&content, ragged)
+      .unwrap_or_else(|e| panic!("failed to parse grid: {e}"));
 
-    Self {
-      width,
-      height,
-      content,
-    }
+    Self { grid }
   }
 
-  fn char_at(&self, x: usize, y: usize) -> char {
-    self
-      .content
-      .get(y)
-      .and_then(|line| line.get(x))
-      .copied()
-      .unwrap_or_else(|| {
-        panic!(
-          "failed to get character at x = {}, y = {} (width = {}, height = {})",
-          x, y, self.width, self.height
-        )
-      })
+  fn cells(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+    (0..self.grid.height())
+      .flat_map(|y| (0..self.grid.width()).map(move |x| (x, y)))
   }
 
-  fn part_1(&self) -> u32 {
-    const MATCH_STRING: &str = "XMAS";
+  /// Yields the cells of every `XMAS` match, from the `X` to the `S`.
+  fn xmas_matches(&self) -> impl Iterator<Item = [(usize, usize); 4]> + '_ {
+    const MATCH_STRING: &[u8] = b"XMAS";
 
-    (0..self.height)
-      .flat_map(|y| (0..self.width).map(move |x| (x, y)))
-      .filter(|(x, y)| {
-        self.char_at(*x, *y) == MATCH_STRING.chars().next().unwrap()
-      })
-      .map(|(x, y)| {
-        DIRECTIONS
-          .iter()
-          .filter(|(dx, dy)| {
-            (1..MATCH_STRING.len()).all(|i| {
-              let x = (x as i32) + (i as i32) * dx;
-              let y = (y as i32) + (i as i32) * dy;
-
-              if x < 0
-                || x >= self.width as i32
-                || y < 0
-                || y >= self.height as i32
-              {
-                return false;
-              }
-
-              self.char_at(x as usize, y as usize)
-                == MATCH_STRING.chars().nth(i).unwrap()
-            })
-          })
-          .count() as u32
+    self
+      .cells()
+      .filter(|(x, y)| self.grid.get(*x, *y) == MATCH_STRING[0])
+      .flat_map(move |(x, y)| {
+        Direction8::iter().filter_map(move |dir| {
+          let (dx, dy) = dir.vector();
+          let cells = std::array::from_fn(|i| {
+            let i = i as isize;
+            ((x as isize + i * dx) as i32, (y as isize + i * dy) as i32)
+          });
+          let matches = (1..MATCH_STRING.len()).all(|i| {
+            let (x, y) = cells[i];
+            self.grid.get_signed(x, y) == Some(MATCH_STRING[i])
+          });
+
+          matches.then(|| cells.map(|(x, y)| (x as usize, y as usize)))
+        })
       })
-      .sum()
-  }
-
-  fn part_2(&self) -> u32 {
-    (0..self.height)
-      .flat_map(|y| (0..self.width).map(move |x| (x, y)))
-      .filter(|(x, y)| self.char_at(*x, *y) == 'A')
-      .filter(|(x, y)| {
-        let x: i32 = *x as i32;
-        let y: i32 = *y as i32;
-
-        if (x - 1) < 0
-          || (x + 1) >= self.width as i32
-          || (y - 1) < 0
-          || (y + 1) >= self.height as i32
-        {
-          return false;
-        }
+  }
 
-        let upper_left = self.char_at((x - 1) as usize, (y - 1) as usize);
-        let upper_right = self.char_at((x + 1) as usize, (y - 1) as usize);
-        let lower_left = self.char_at((x - 1) as usize, (y + 1) as usize);
-        let lower_right = self.char_at((x + 1) as usize, (y + 1) as usize);
+  /// Yields the cells of every X-MAS cross: the central `A`, then the upper
+  /// left, upper right, lower left and lower right corners.
+  fn x_mas_matches(&self) -> impl Iterator<Item = [(usize, usize); 5]> + '_ {
+    self
+      .cells()
+      .filter(|(x, y)| self.grid.get(*x, *y) == b'A')
+      .filter_map(|(x, y)| {
+        let cells = [
+          (x as i32, y as i32),
+          (x as i32 - 1, y as i32 - 1),
+          (x as i32 + 1, y as i32 - 1),
+          (x as i32 - 1, y as i32 + 1),
+          (x as i32 + 1, y as i32 + 1),
+        ];
+        let [
+          _,
+          Some(upper_left),
+          Some(upper_right),
+          Some(lower_left),
+          Some(lower_right),
+        ] = cells.map(|(x, y)| self.grid.get_signed(x, y))
+        else {
+          return None;
+        };
 
         // diagonals must match "MAS", forward or backward
         match (upper_left, lower_right) {
-          ('M', 'S') | ('S', 'M') => (),
-          _ => return false,
+          (b'M', b'S') | (b'S', b'M') => (),
+          _ => return None,
         }
         match (upper_right, lower_left) {
-          ('M', 'S') | ('S', 'M') => (),
-          _ => return false,
+          (b'M', b'S') | (b'S', b'M') => (),
+          _ => return None,
         }
 
-        true
+        Some(cells.map(|(x, y)| (x as usize, y as usize)))
       })
-      .count() as u32
   }
+
+  fn part_1(&self) -> u32 { self.xmas_matches().count() as u32 }
+
+  fn part_2(&self) -> u32 { self.x_mas_matches().count() as u32 }
+}
+
+/// Prints a generated puzzle to stdout, and its expected answers to stderr.
+///
+/// Usage: `generate <width> <height> <seed> [words...]`
+fn generate(args: &[String]) {
+  let parse_arg = |i: usize, name: &str| -> u64 {
+    args
+      .get(i)
+      .unwrap_or_else(|| panic!("missing {name} argument"))
+      .parse()
+      .unwrap_or_else(|_| panic!("failed to parse {name} argument"))
+  };
+
+  let mut words = args.get(3..).unwrap_or_default().to_vec();
+  if words.is_empty() {
+    words = vec!["XMAS".to_string(); 8];
+  }
+
+  let puzzle = generate::Config {
+    width: parse_arg(0, "width") as usize,
+    height: parse_arg(1, "height") as usize,
+    seed: parse_arg(2, "seed"),
+    words,
+    distractors: b"XMAS".to_vec(),
+  }
+  .generate()
+  .unwrap_or_else(|e| panic!("failed to generate puzzle: {e}"));
+
+  print!("{}", puzzle.text);
+  eprintln!("Placed {} words", puzzle.placed);
+  eprintln!("Part 1: {}", puzzle.part_1);
+  eprintln!("Part 2: {}", puzzle.part_2);
+}
+
+/// Times parsing and both parts with the byte grid against the old grid of
+/// `char`s, on a generated square puzzle.
+///
+/// Usage: `bench [size] [seed]`, with a 1000x1000 grid by default.
+fn bench(args: &[String]) {
+  let arg = |i: usize, default: u64| -> u64 {
+    args.get(i).map_or(default, |arg| {
+      arg
+        .parse()
+        .unwrap_or_else(|_| panic!("failed to parse argument {arg:?}"))
+    })
+  };
+  let size = arg(0, 1000) as usize;
+  let text = generate::Config {
+    width:       size,
+    height:      size,
+    seed:        arg(1, 0),
+    words:       vec!["XMAS".to_string(); size * size / 20],
+    distractors: b"XMAS".to_vec(),
+  }
+  .generate()
+  .unwrap_or_else(|e| panic!("failed to generate puzzle: {e}"))
+  .text;
+
+  fn timed<T>(f: impl FnOnce() -> T) -> (T, f32) {
+    let now = std::time::Instant::now();
+    let result = f();
+    (result, now.elapsed().as_secs_f32() * 1000.0)
+  }
+
+  let (chars, chars_ms) = timed(|| bench::CharGrid::parse(&text));
+  let (bytes, bytes_ms) =
+    timed(|| Input::from_string(text.clone(), RaggedRows::Reject));
+  println!("Parsed chars in {chars_ms:.3}ms, bytes in {bytes_ms:.3}ms");
+
+  let (chars_1, chars_ms) = timed(|| chars.part_1());
+  let (bytes_1, bytes_ms) = timed(|| bytes.part_1());
+  println!(
+    "Part 1: {bytes_1}, chars in {chars_ms:.3}ms, bytes in {bytes_ms:.3}ms"
+  );
+  let (chars_2, chars_ms) = timed(|| chars.part_2());
+  let (bytes_2, bytes_ms) = timed(|| bytes.part_2());
+  println!(
+    "Part 2: {bytes_2}, chars in {chars_ms:.3}ms, bytes in {bytes_ms:.3}ms"
+  );
+
+  assert_eq!(chars_1, bytes_1, "part 1 differs between grids");
+  assert_eq!(chars_2, bytes_2, "part 2 differs between grids");
 }
 
 fn main() {
+  let args: Vec<_> = std::env::args().skip(1).collect();
+  if args.first().is_some_and(|arg| arg == "generate") {
+    generate(&args[1..]);
+    return;
+  }
+  if args.first().is_some_and(|arg| arg == "bench") {
+    bench(&args[1..]);
+    return;
+  }
+
+  // `--strict` rejects ragged rows instead of padding them with a byte that can
+  // never be part of a match
+  let ragged = if args.iter().any(|arg| arg == "--strict") {
+    RaggedRows::Reject
+  } else {
+    RaggedRows::Pad(b'.')
+  };
+
   let now = std::time::Instant::now();
-  let input = Input::from_file(PathBuf::from("inputs/day_04.txt").as_path());
+  let input =
+    Input::from_file(PathBuf::from("inputs/day_04.txt").as_path(), ragged);
   println!(
     "Parsed input in {:.3}ms",
     now.elapsed().as_secs_f32() * 1000.0
   );
 
+  // `--show` highlights the matched cells with ANSI colours, and
+  // `--show=plain` replaces the unmatched cells with `.`
+  let style = args.iter().find_map(|arg| match arg.as_str() {
+    "--show" => Some(Style::Ansi),
+    "--show=plain" => Some(Style::Plain),
+    _ => None,
+  });
+  if let Some(style) = style {
+    let part_1_mask = show::mask(&input.grid, input.xmas_matches());
+    println!("Part 1 matches:");
+    print!("{}", show::render(&input.grid, &part_1_mask, style));
+    let part_2_mask = show::mask(&input.grid, input.x_mas_matches());
+    println!("Part 2 matches:");
+    print!("{}", show::render(&input.grid, &part_2_mask, style));
+  }
+
   let now = std::time::Instant::now();
   let part_1 = input.part_1();
   println!(
@@ -174,13 +264,163 @@ MXMXAXMASX
 
   #[test]
   fn test_part_1() {
-    let input = Input::from_string(MINI_INPUT.to_string());
+    let input = Input::from_string(MINI_INPUT.to_string(), RaggedRows::Reject);
     assert_eq!(input.part_1(), 18);
   }
 
   #[test]
   fn test_part_2() {
-    let input = Input::from_string(MINI_INPUT.to_string());
+    let input = Input::from_string(MINI_INPUT.to_string(), RaggedRows::Reject);
+    assert_eq!(input.part_2(), 9);
+  }
+
+  #[test]
+  fn char_grid_matches_byte_grid() {
+    let chars = bench::CharGrid::parse(MINI_INPUT);
+    assert_eq!(chars.part_1(), 18);
+    assert_eq!(chars.part_2(), 9);
+
+    for seed in 0..4 {
+      let text = generate::Config {
+        width: 30,
+        height: 20,
+        seed,
+        words: vec!["XMAS".to_string(); 30],
+        distractors: b"XMAS".to_vec(),
+      }
+      .generate()
+      .unwrap()
+      .text;
+      let chars = bench::CharGrid::parse(&text);
+      let bytes = Input::from_string(text, RaggedRows::Reject);
+      assert_eq!(chars.part_1(), bytes.part_1(), "seed {seed}");
+      assert_eq!(chars.part_2(), bytes.part_2(), "seed {seed}");
+    }
+  }
+
+  #[test]
+  fn show_matches() {
+    const PART_1_MATCHES: &str = r#"
+....XXMAS.
+.SAMXMS...
+...S..A...
+..A.A.MS.X
+XMASAMX.MM
+X.....XA.A
+S.S.S.S.SS
+.A.A.A.A.A
+..M.M.M.MM
+.X.X.XMASX
+"#;
+    const PART_2_MATCHES: &str = r#"
+.M.S......
+..A..MSMS.
+.M.S.MAA..
+..A.ASMSM.
+.M.S.M....
+..........
+S.S.S.S.S.
+.A.A.A.A..
+M.M.M.M.M.
+..........
+"#;
+
+    let input = Input::from_string(MINI_INPUT.to_string(), RaggedRows::Reject);
+
+    let mask = show::mask(&input.grid, input.xmas_matches());
+    assert_eq!(
+      show::render(&input.grid, &mask, Style::Plain),
+      PART_1_MATCHES.trim_start()
+    );
+    let mask = show::mask(&input.grid, input.x_mas_matches());
+    assert_eq!(
+      show::render(&input.grid, &mask, Style::Plain),
+      PART_2_MATCHES.trim_start()
+    );
+  }
+
+  #[test]
+  fn crlf_and_ragged_rows() {
+    let crlf = MINI_INPUT.replace('\n', "\r\n");
+    let input = Input::from_string(crlf, RaggedRows::Reject);
+    assert_eq!(input.part_1(), 18);
     assert_eq!(input.part_2(), 9);
+
+    // trailing spaces are stripped, and short rows are padded
+    let ragged = "XMAS  \nXMA\nX\n";
+    let input = Input::from_string(ragged.to_string(), RaggedRows::Pad(b'.'));
+    assert_eq!((input.grid.width(), input.grid.height()), (4, 3));
+    assert_eq!(input.part_1(), 1);
+
+    assert_eq!(
+      Grid::parse(ragged, RaggedRows::Reject).unwrap_err(),
+      grid::GridError::RaggedRow {
+        line:     2,
+        len:      3,
+        expected: 4,
+      }
+    );
+  }
+
+  #[test]
+  fn generated_puzzles() {
+    for seed in 0..50 {
+      let puzzle = generate::Config {
+        width: 5 + seed as usize % 20,
+        height: 5 + seed as usize % 13,
+        seed,
+        words: ["XMAS", "XMAS", "XMAS", "MAS", "MAS", "MAS", "MAS"]
+          .map(String::from)
+          .to_vec(),
+        distractors: b"XMAS".to_vec(),
+      }
+      .generate()
+      .unwrap();
+
+      let input = Input::from_string(puzzle.text, RaggedRows::Reject);
+      assert_eq!(input.part_1(), puzzle.part_1, "part 1, seed {seed}");
+      assert_eq!(input.part_2(), puzzle.part_2, "part 2, seed {seed}");
+    }
+  }
+
+  #[test]
+  fn generated_puzzle_without_distractor_matches() {
+    // with distractors that can't form a match, every `XMAS` is a placed one
+    let puzzle = generate::Config {
+      width:       12,
+      height:      12,
+      seed:        4,
+      words:       vec!["XMAS".to_string(); 6],
+      distractors: b"BCD".to_vec(),
+    }
+    .generate()
+    .unwrap();
+
+    assert_eq!(puzzle.placed, 6);
+    let input = Input::from_string(puzzle.text, RaggedRows::Reject);
+    assert_eq!(input.part_1(), puzzle.part_1);
+    assert!(puzzle.part_1 >= 6);
+  }
+
+  #[test]
+  fn generated_puzzle_counts_distinct_placements() {
+    let config = |width, height| generate::Config {
+      width,
+      height,
+      seed: 0,
+      words: vec!["XMAS".to_string(); 3],
+      distractors: b"B".to_vec(),
+    };
+
+    // there is only room for one `XMAS`, and laying it again on top of
+    // itself isn't another placement
+    let puzzle = config(4, 1).generate().unwrap();
+    assert_eq!(puzzle.placed, 1);
+    assert_eq!(puzzle.part_1, 1);
+
+    assert_eq!(
+      config(0, 5).generate().err(),
+      Some(generate::GenerateError::EmptyGrid)
+    );
   }
 }
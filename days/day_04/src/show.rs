@@ -0,0 +1,53 @@
+use crate::grid::Grid;
+
+const HIGHLIGHT: &str = "\x1b[1;33m";
+const DIM: &str = "\x1b[2m";
+const RESET: &str = "\x1b[0m";
+
+#[derive(Clone, Copy, Debug)]
+pub enum Style {
+  /// Matched cells are bold and coloured, unmatched cells are dimmed.
+  Ansi,
+  /// Unmatched cells are replaced with `.`, like the puzzle illustrations.
+  Plain,
+}
+
+/// Collects the cells of a set of matches into a row-major mask.
+pub fn mask<const N: usize>(
+  grid: &Grid,
+  matches: impl Iterator<Item = [(usize, usize); N]>,
+) -> Vec<bool> {
+  let mut mask = vec![false; grid.width() * grid.height()];
+  for (x, y) in matches.flatten() {
+    mask[y * grid.width() + x] = true;
+  }
+  mask
+}
+
+/// Renders the grid, highlighting the cells set in `mask`.
+pub fn render(grid: &Grid, mask: &[bool], style: Style) -> String {
+  let mut out = String::new();
+
+  for y in 0..grid.height() {
+    for x in 0..grid.width() {
+      let c = grid.get(x, y) as char;
+      let matched = mask[y * grid.width() + x];
+      match (style, matched) {
+        (Style::Ansi, true) => {
+          out.push_str(HIGHLIGHT);
+          out.push(c);
+          out.push_str(RESET);
+        }
+        (Style::Ansi, false) => {
+          out.push_str(DIM);
+          out.push(c);
+          out.push_str(RESET);
+        }
+        (Style::Plain, true) => out.push(c),
+        (Style::Plain, false) => out.push('.'),
+      }
+    }
+    out.push('\n');
+  }
+  out
+}
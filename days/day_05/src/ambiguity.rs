@@ -0,0 +1,139 @@
+use std::collections::HashMap;
+
+use crate::{CycleError, Input, MAX_SEARCH_PAGES, SearchError};
+
+/// The orders that comply with the rules for a page set, when the rules
+/// don't fix a single one.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Ambiguity {
+  /// How many orders comply with the rules, saturating at `u64::MAX`.
+  pub orders:       u64,
+  /// Every page that is in the middle of at least one of those orders.
+  pub middle_pages: Vec<u32>,
+}
+
+impl Input {
+  /// Counts the orders of a page set that comply with the rules, and finds
+  /// the pages that can end up in the middle.
+  ///
+  /// Like [`Input::disorder`], counting is fast when the rules nearly fix
+  /// the order, and exponential when they leave most pages unordered.
+  pub fn ambiguity(&self, page_set: &[u32]) -> Result<Ambiguity, SearchError> {
+    let middle_pages = self.middle_pages(page_set)?;
+    if page_set.len() > MAX_SEARCH_PAGES {
+      return Err(SearchError::TooManyPages(page_set.len()));
+    }
+
+    let precedes = self.index.closure(page_set);
+    Ok(Ambiguity {
+      orders: count_orders(&precedes, 0, &mut HashMap::new()),
+      middle_pages,
+    })
+  }
+
+  /// Finds the pages that are in the middle of at least one of the orders of
+  /// a page set that comply with the rules. Unlike counting the orders, this
+  /// takes polynomial time.
+  pub fn middle_pages(&self, page_set: &[u32]) -> Result<Vec<u32>, CycleError> {
+    self.correct(page_set)?;
+
+    let precedes = self.index.closure(page_set);
+    let n = page_set.len();
+    let middle = n / 2;
+
+    // a page can be at any index that leaves room for the pages that must
+    // come before it and the pages that must come after it
+    Ok(
+      (0..n)
+        .filter(|&j| {
+          let before = (0..n).filter(|&i| precedes[i][j]).count();
+          let after = (0..n).filter(|&k| precedes[j][k]).count();
+          before <= middle && after < n - middle
+        })
+        .map(|j| page_set[j])
+        .collect(),
+    )
+  }
+
+  /// Lists up to `limit` of the orders of a page set that comply with the
+  /// rules.
+  pub fn valid_orders(
+    &self,
+    page_set: &[u32],
+    limit: usize,
+  ) -> Result<Vec<Vec<u32>>, CycleError> {
+    self.correct(page_set)?;
+
+    let precedes = self.index.closure(page_set);
+    let mut orders = Vec::new();
+    let mut order = Vec::with_capacity(page_set.len());
+    let mut placed = vec![false; page_set.len()];
+    extend_orders(&precedes, &mut order, &mut placed, &mut orders, limit);
+
+    Ok(
+      orders
+        .into_iter()
+        .map(|order| order.into_iter().map(|i| page_set[i]).collect())
+        .collect(),
+    )
+  }
+}
+
+/// The indices of the pages that aren't placed yet, but have every page that
+/// must come before them placed.
+fn ready(
+  precedes: &[Vec<bool>],
+  is_placed: impl Fn(usize) -> bool,
+) -> Vec<usize> {
+  let n = precedes.len();
+  (0..n)
+    .filter(|&j| !is_placed(j))
+    .filter(|&j| (0..n).all(|i| !precedes[i][j] || is_placed(i)))
+    .collect()
+}
+
+/// Counts the valid orders of the pages that aren't in `placed` yet.
+fn count_orders(
+  precedes: &[Vec<bool>],
+  placed: u64,
+  memo: &mut HashMap<u64, u64>,
+) -> u64 {
+  if placed.count_ones() as usize == precedes.len() {
+    return 1;
+  }
+  if let Some(&count) = memo.get(&placed) {
+    return count;
+  }
+
+  let count = ready(precedes, |i| placed & (1 << i) != 0)
+    .into_iter()
+    .map(|j| count_orders(precedes, placed | (1 << j), memo))
+    .fold(0, u64::saturating_add);
+
+  memo.insert(placed, count);
+  count
+}
+
+fn extend_orders(
+  precedes: &[Vec<bool>],
+  order: &mut Vec<usize>,
+  placed: &mut [bool],
+  orders: &mut Vec<Vec<usize>>,
+  limit: usize,
+) {
+  if orders.len() >= limit {
+    return;
+  }
+  if order.len() == precedes.len() {
+    orders.push(order.clone());
+    return;
+  }
+
+  for j in ready(precedes, |i| placed[i]) {
+    placed[j] = true;
+    order.push(j);
+    extend_orders(precedes, order, placed, orders, limit);
+    order.pop();
+    placed[j] = false;
+  }
+}
@@ -0,0 +1,99 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use crate::{PageOrderingRule, rules::RuleIndex};
+
+/// Problems with the rules that apply to a set of pages.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct RuleAnalysis {
+  /// Pairs of pages with a rule in each direction, smaller page first.
+  pub contradictions: Vec<(u32, u32)>,
+  /// Groups of pages that are all on a common cycle, so none of them can be
+  /// placed before the others. Includes the pairs in `contradictions`.
+  pub cycles:         Vec<Vec<u32>>,
+  /// Rules that are implied by a chain of other rules. Removing all of them
+  /// gives the transitive reduction. Rules between pages on the same cycle
+  /// are never counted, since the reduction of a cycle isn't unique. Nor are
+  /// several rules from one cycle to the same page, since any one of them
+  /// would do.
+  pub redundant:      Vec<PageOrderingRule>,
+}
+
+impl RuleAnalysis {
+  /// Analyzes the subgraph of the rules induced by `pages`.
+  pub fn new(index: &RuleIndex, pages: &[u32]) -> Self {
+    let pages: BTreeSet<u32> = pages.iter().copied().collect();
+    let successors =
+      |page: u32| index.successors(page).filter(|next| pages.contains(next));
+
+    // the set of pages reachable from each page, by at least one rule
+    let reachable: HashMap<u32, HashSet<u32>> = pages
+      .iter()
+      .map(|&page| {
+        let mut seen = HashSet::new();
+        let mut stack: Vec<u32> = successors(page).collect();
+        while let Some(next) = stack.pop() {
+          if seen.insert(next) {
+            stack.extend(successors(next));
+          }
+        }
+        (page, seen)
+      })
+      .collect();
+    let on_common_cycle =
+      |a: u32, b: u32| reachable[&a].contains(&b) && reachable[&b].contains(&a);
+
+    let mut analysis = Self::default();
+
+    for &a in &pages {
+      for b in successors(a) {
+        if a < b && index.must_precede(b, a) {
+          analysis.contradictions.push((a, b));
+        }
+      }
+    }
+
+    let mut assigned = HashSet::new();
+    for &a in &pages {
+      if assigned.contains(&a) || !reachable[&a].contains(&a) {
+        continue;
+      }
+      let cycle: Vec<u32> = pages
+        .iter()
+        .copied()
+        .filter(|&b| on_common_cycle(a, b))
+        .collect();
+      assigned.extend(cycle.iter().copied());
+      analysis.cycles.push(cycle);
+    }
+
+    // with each cycle collapsed into a single node, a rule `a|b` is implied
+    // if there is a path from `a` to `b` through some other node. Looking for
+    // any other path isn't enough: when `a` is on a cycle, that path can lead
+    // back through `a|b` itself
+    let same_node = |a: u32, b: u32| a == b || on_common_cycle(a, b);
+    for &a in &pages {
+      let mut after: Vec<u32> = successors(a).collect();
+      after.sort();
+      for &b in &after {
+        if same_node(a, b) {
+          continue;
+        }
+        let implied = reachable[&a].iter().any(|&other| {
+          !same_node(a, other)
+            && !same_node(b, other)
+            && reachable[&other].contains(&b)
+        });
+        if implied {
+          analysis.redundant.push(PageOrderingRule {
+            before: a,
+            after:  b,
+          });
+        }
+      }
+    }
+
+    analysis
+  }
+
+  pub fn is_consistent(&self) -> bool { self.cycles.is_empty() }
+}
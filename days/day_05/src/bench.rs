@@ -0,0 +1,111 @@
+use std::collections::HashSet;
+
+use rand::{Rng, SeedableRng, rngs::StdRng, seq::SliceRandom};
+
+use crate::{Input, PageOrderingRule, rules::RuleIndex};
+
+pub struct Config {
+  pub pages:      u32,
+  pub rules:      usize,
+  pub updates:    usize,
+  pub update_len: usize,
+  pub seed:       u64,
+}
+
+/// Builds an input whose rules are consistent with a hidden random order of
+/// the pages. Half of the updates follow that order, and the rest are
+/// shuffled.
+pub fn synthetic_input(config: &Config) -> Input {
+  let mut rng = StdRng::seed_from_u64(config.seed);
+
+  let mut order: Vec<u32> = (0..config.pages).collect();
+  order.shuffle(&mut rng);
+
+  let pages = config.pages as usize;
+  let max_rules = pages * pages.saturating_sub(1) / 2;
+  let mut rules = HashSet::new();
+  while rules.len() < config.rules.min(max_rules) {
+    let i = rng.gen_range(0..pages);
+    let j = rng.gen_range(0..pages);
+    if i == j {
+      continue;
+    }
+    rules.insert(PageOrderingRule {
+      before: order[i.min(j)],
+      after:  order[i.max(j)],
+    });
+  }
+
+  let page_sets: Vec<Vec<u32>> = (0..config.updates)
+    .map(|u| {
+      let mut positions =
+        rand::seq::index::sample(&mut rng, pages, config.update_len.min(pages))
+          .into_vec();
+      if u % 2 == 0 {
+        positions.sort();
+      }
+      positions.into_iter().map(|i| order[i]).collect()
+    })
+    .collect();
+
+  Input {
+    index: RuleIndex::new(&rules),
+    // the lines the updates would be on if the input were written out
+    update_lines: (0..page_sets.len()).map(|i| rules.len() + 2 + i).collect(),
+    rules,
+    page_sets,
+  }
+}
+
+/// Times the rule index against the original rule scan on a synthetic input.
+///
+/// Usage: `bench [pages] [rules] [updates] [update_len] [seed]`
+pub fn run(args: &[String]) {
+  let arg = |i: usize, default: u64| -> u64 {
+    args.get(i).map_or(default, |arg| {
+      arg
+        .parse()
+        .unwrap_or_else(|_| panic!("failed to parse argument {arg:?}"))
+    })
+  };
+  let config = Config {
+    pages:      arg(0, 1000) as u32,
+    rules:      arg(1, 100_000) as usize,
+    updates:    arg(2, 1000) as usize,
+    update_len: arg(3, 23) as usize,
+    seed:       arg(4, 0),
+  };
+
+  let now = std::time::Instant::now();
+  let input = synthetic_input(&config);
+  println!(
+    "Built {} rules and {} updates in {:.3}ms",
+    input.rules.len(),
+    input.page_sets.len(),
+    now.elapsed().as_secs_f32() * 1000.0
+  );
+
+  let now = std::time::Instant::now();
+  let scanned = input
+    .page_sets
+    .iter()
+    .filter(|page_set| input.complies_with_rules_scan(page_set))
+    .count();
+  println!(
+    "Rule scan: {scanned} compliant, in {:.3}ms",
+    now.elapsed().as_secs_f32() * 1000.0
+  );
+
+  let now = std::time::Instant::now();
+  let indexed = input
+    .page_sets
+    .iter()
+    .filter(|page_set| input.complies_with_rules(page_set))
+    .count();
+  println!(
+    "Rule index: {indexed} compliant, in {:.3}ms",
+    now.elapsed().as_secs_f32() * 1000.0
+  );
+
+  assert_eq!(scanned, indexed, "rule index disagrees with rule scan");
+}
@@ -0,0 +1,107 @@
+use std::collections::HashMap;
+
+use crate::{Input, MAX_SEARCH_PAGES, SearchError};
+
+/// How far an update is from complying with the rules.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Disorder {
+  /// The fewest swaps of neighbouring pages that make the update comply. This
+  /// is the Kendall tau distance to the nearest valid order.
+  pub swaps: usize,
+  /// The fewest pages that have to be taken out and reinserted elsewhere.
+  pub moves: usize,
+}
+
+impl Input {
+  /// Measures how disordered a page set is. The rules may leave some pages
+  /// unordered, in which case the distance is to the closest of the valid
+  /// orders.
+  ///
+  /// Finding the fewest swaps searches the sets of pages that can start a
+  /// valid order, so it is fast when the rules nearly fix the order (as in
+  /// the puzzle input) and exponential when they leave most pages unordered.
+  pub fn disorder(&self, page_set: &[u32]) -> Result<Disorder, SearchError> {
+    // reject cycles, which have no valid order to be close to
+    self.correct(page_set)?;
+    if page_set.len() > MAX_SEARCH_PAGES {
+      return Err(SearchError::TooManyPages(page_set.len()));
+    }
+
+    let precedes = self.index.closure(page_set);
+
+    Ok(Disorder {
+      swaps: fewest_swaps(&precedes, 0, &mut HashMap::new()),
+      moves: fewest_moves(&precedes),
+    })
+  }
+}
+
+/// Builds the valid order one page at a time. Placing a page next costs one
+/// swap for every page after it in the original order that is already
+/// placed. `placed` is a bitmask over the original indices.
+fn fewest_swaps(
+  precedes: &[Vec<bool>],
+  placed: u64,
+  memo: &mut HashMap<u64, usize>,
+) -> usize {
+  let n = precedes.len();
+  if placed.count_ones() as usize == n {
+    return 0;
+  }
+  if let Some(&swaps) = memo.get(&placed) {
+    return swaps;
+  }
+
+  let is_placed = |i: usize| placed & (1 << i) != 0;
+  let swaps = (0..n)
+    .filter(|&j| !is_placed(j))
+    // every page that must come before `j` has to be placed already
+    .filter(|&j| (0..n).all(|i| !precedes[i][j] || is_placed(i)))
+    .map(|j| {
+      let cost = (j + 1..n).filter(|&i| is_placed(i)).count();
+      cost + fewest_swaps(precedes, placed | (1 << j), memo)
+    })
+    .min()
+    .expect("no page can be placed next");
+
+  memo.insert(placed, swaps);
+  swaps
+}
+
+/// The pages that stay put must not contain a pair that is in the wrong
+/// order. "The later page must come before the earlier one" is a partial
+/// order on the indices, so by Dilworth's theorem the most pages that can
+/// stay is `n` minus a maximum matching on it, and the rest have to move.
+fn fewest_moves(precedes: &[Vec<bool>]) -> usize {
+  let n = precedes.len();
+  let wrong_order = |i: usize, j: usize| i < j && precedes[j][i];
+
+  // Kuhn's augmenting path algorithm, matching each `i` to a later `j`
+  fn augment(
+    i: usize,
+    wrong_order: &dyn Fn(usize, usize) -> bool,
+    matched_to: &mut [Option<usize>],
+    seen: &mut [bool],
+  ) -> bool {
+    for j in 0..matched_to.len() {
+      if !wrong_order(i, j) || seen[j] {
+        continue;
+      }
+      seen[j] = true;
+      let free = match matched_to[j] {
+        None => true,
+        Some(other) => augment(other, wrong_order, matched_to, seen),
+      };
+      if free {
+        matched_to[j] = Some(i);
+        return true;
+      }
+    }
+    false
+  }
+
+  let mut matched_to = vec![None; n];
+  (0..n)
+    .filter(|&i| augment(i, &wrong_order, &mut matched_to, &mut vec![false; n]))
+    .count()
+}
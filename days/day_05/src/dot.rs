@@ -0,0 +1,59 @@
+use std::{collections::BTreeSet, fmt::Write};
+
+use crate::{Input, PageOrderingRule};
+
+impl Input {
+  /// Renders the rule graph in Graphviz DOT format. With an update, only the
+  /// rules between its pages are drawn, nodes are labelled with their index
+  /// in the update, and the rules it breaks are highlighted.
+  pub fn to_dot(&self, page_set: Option<&[u32]>) -> String {
+    let mut out = String::new();
+    writeln!(out, "digraph rules {{").unwrap();
+    writeln!(out, "  node [shape=circle];").unwrap();
+
+    let (pages, violated): (BTreeSet<u32>, BTreeSet<_>) = match page_set {
+      Some(page_set) => {
+        for (i, page) in page_set.iter().enumerate() {
+          writeln!(out, "  {page} [xlabel=\"#{i}\"];").unwrap();
+        }
+        (
+          page_set.iter().copied().collect(),
+          self
+            .violations(page_set)
+            .into_iter()
+            .map(|v| v.rule)
+            .collect(),
+        )
+      }
+      None => (
+        self
+          .rules
+          .iter()
+          .flat_map(|rule| [rule.before, rule.after])
+          .collect(),
+        BTreeSet::new(),
+      ),
+    };
+
+    let mut rules: Vec<_> = self
+      .rules
+      .iter()
+      .filter(|rule| {
+        pages.contains(&rule.before) && pages.contains(&rule.after)
+      })
+      .collect();
+    rules.sort_by_key(|rule| (rule.before, rule.after));
+
+    for rule @ PageOrderingRule { before, after } in rules {
+      if violated.contains(rule) {
+        writeln!(out, "  {before} -> {after} [color=red, penwidth=2];")
+          .unwrap();
+      } else {
+        writeln!(out, "  {before} -> {after};").unwrap();
+      }
+    }
+
+    writeln!(out, "}}").unwrap();
+    out
+  }
+}
@@ -0,0 +1,161 @@
+use std::fmt;
+
+use crate::{CycleError, Input, PageOrderingRule};
+
+/// A rule that an update breaks: the rule's `before` page appears at
+/// `before_index`, after its `after` page at `after_index`.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Violation {
+  pub rule:         PageOrderingRule,
+  pub before_index: usize,
+  pub after_index:  usize,
+}
+
+/// Taking a page out of the update and reinserting it elsewhere.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Move {
+  pub page:    u32,
+  /// The page's index in the original order.
+  pub from:    usize,
+  /// The page's index in the corrected order, once every move is done. This
+  /// can equal `from` when other pages move past it.
+  pub ends_at: usize,
+}
+
+/// Why an update breaks the rules, and how it was corrected.
+#[derive(Debug)]
+pub struct Report {
+  pub original:   Vec<u32>,
+  pub violations: Vec<Violation>,
+  pub corrected:  Result<Vec<u32>, CycleError>,
+  /// The fewest moves that turn the original order into the corrected one.
+  pub moves:      Vec<Move>,
+}
+
+impl Input {
+  /// Lists every rule that the page set breaks, in order of the later page.
+  pub fn violations(&self, page_set: &[u32]) -> Vec<Violation> {
+    let mut violations = Vec::new();
+    for (after_index, &after) in page_set.iter().enumerate() {
+      for (before_index, &before) in
+        page_set.iter().enumerate().skip(after_index + 1)
+      {
+        if self.index.must_precede(before, after) {
+          violations.push(Violation {
+            rule: PageOrderingRule { before, after },
+            before_index,
+            after_index,
+          });
+        }
+      }
+    }
+    violations.sort_by_key(|v| (v.before_index, v.after_index));
+    violations
+  }
+
+  /// Explains a page set, or returns `None` if it already complies.
+  pub fn explain(&self, page_set: &[u32]) -> Option<Report> {
+    let violations = self.violations(page_set);
+    if violations.is_empty() {
+      return None;
+    }
+
+    let corrected = self.correct(page_set);
+    let moves = match &corrected {
+      Ok(corrected) => moves(page_set, corrected),
+      Err(_) => Vec::new(),
+    };
+
+    Some(Report {
+      original: page_set.to_vec(),
+      violations,
+      corrected,
+      moves,
+    })
+  }
+}
+
+/// The pages that have to move to turn `original` into `target`, a
+/// permutation of it. Every other page keeps its relative order, and those
+/// pages are the longest subsequence that the two orders share.
+fn moves(original: &[u32], target: &[u32]) -> Vec<Move> {
+  let target_index = |page: u32| {
+    target
+      .iter()
+      .position(|&other| other == page)
+      .expect("target is not a permutation of the original")
+  };
+  let positions: Vec<usize> =
+    original.iter().map(|&page| target_index(page)).collect();
+
+  let stay = longest_increasing_subsequence(&positions);
+  (0..original.len())
+    .filter(|i| !stay.contains(i))
+    .map(|from| Move {
+      page: original[from],
+      from,
+      ends_at: positions[from],
+    })
+    .collect()
+}
+
+/// Returns the indices of a longest strictly increasing subsequence.
+pub fn longest_increasing_subsequence(values: &[usize]) -> Vec<usize> {
+  // `tails[k]` is the index of the smallest value ending an increasing run of
+  // length `k + 1`, and `previous` links each index to the one before it
+  let mut tails: Vec<usize> = Vec::new();
+  let mut previous = vec![None; values.len()];
+  for (i, &value) in values.iter().enumerate() {
+    let k = tails.partition_point(|&t| values[t] < value);
+    previous[i] = k.checked_sub(1).map(|k| tails[k]);
+    if k == tails.len() {
+      tails.push(i);
+    } else {
+      tails[k] = i;
+    }
+  }
+
+  let mut run = Vec::with_capacity(tails.len());
+  let mut next = tails.last().copied();
+  while let Some(i) = next {
+    run.push(i);
+    next = previous[i];
+  }
+  run.reverse();
+  run
+}
+
+impl fmt::Display for Report {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    writeln!(f, "Update {:?}", self.original)?;
+    for Violation {
+      rule: PageOrderingRule { before, after },
+      before_index,
+      after_index,
+    } in &self.violations
+    {
+      writeln!(
+        f,
+        "  breaks {before}|{after}: {before} is at {before_index}, after \
+         {after} at {after_index}"
+      )?;
+    }
+    match &self.corrected {
+      Ok(corrected) => {
+        for Move {
+          page,
+          from,
+          ends_at,
+        } in &self.moves
+        {
+          writeln!(
+            f,
+            "  move {page} from index {from} (ends at index {ends_at})"
+          )?;
+        }
+        writeln!(f, "  corrected to {corrected:?}")
+      }
+      Err(e) => writeln!(f, "  cannot be corrected: {e}"),
+    }
+  }
+}
@@ -1,17 +1,38 @@
+mod ambiguity;
+mod analysis;
+mod bench;
+mod disorder;
+mod dot;
+mod explain;
+mod order;
+mod parse;
+mod rules;
+
 use std::{
-  collections::HashSet,
+  collections::{BTreeSet, HashSet},
+  fmt,
   path::{Path, PathBuf},
 };
 
-#[derive(Hash, Eq, PartialEq, Debug)]
+use self::{
+  analysis::RuleAnalysis,
+  disorder::Disorder,
+  order::{OrderingError, PageOrder},
+  rules::RuleIndex,
+};
+
+#[derive(Clone, Copy, Hash, Eq, PartialEq, Ord, PartialOrd, Debug)]
 struct PageOrderingRule {
   before: u32,
   after:  u32,
 }
 
 struct Input {
-  rules:     HashSet<PageOrderingRule>,
-  page_sets: Vec<Vec<u32>>,
+  rules:        HashSet<PageOrderingRule>,
+  index:        RuleIndex,
+  page_sets:    Vec<Vec<u32>>,
+  /// The line of the input that each page set was parsed from.
+  update_lines: Vec<usize>,
 }
 
 impl Input {
@@ -23,40 +44,35 @@ impl Input {
   }
 
   fn from_string(content: String) -> Self {
-    let mut rules = HashSet::new();
-    let mut page_sets = Vec::new();
-
-    for line in content.lines() {
-      if line.contains('|') {
-        let rule_parts = line.split('|').collect::<Vec<_>>();
-        rules.insert(PageOrderingRule {
-          before: rule_parts[0]
-            .trim()
-            .parse()
-            .expect("failed to parse \"before\" number"),
-          after:  rule_parts[1]
-            .trim()
-            .parse()
-            .expect("failed to parse \"after\" number"),
-        });
-      } else {
-        if line.is_empty() {
-          continue;
-        }
-
-        page_sets.push(
-          line
-            .split(',')
-            .map(|part| part.parse().expect("failed to parse number"))
-            .collect(),
-        );
-      }
-    }
+    Self::parse(&content)
+      .unwrap_or_else(|e| panic!("failed to parse input: {e}"))
+  }
 
-    Self { rules, page_sets }
+  /// The line numbers of the updates with an even number of pages, which
+  /// have no single middle page.
+  fn even_length_updates(&self) -> Vec<usize> {
+    self
+      .page_sets
+      .iter()
+      .zip(&self.update_lines)
+      .filter(|(page_set, _)| page_set.len() % 2 == 0)
+      .map(|(_, &line)| line)
+      .collect()
   }
 
+  /// Checks every pair of pages against the rule index, so this is quadratic
+  /// in the page set length but independent of the number of rules.
   fn complies_with_rules(&self, page_set: &[u32]) -> bool {
+    page_set.iter().enumerate().all(|(i, &before)| {
+      page_set[i + 1..]
+        .iter()
+        .all(|&after| !self.index.must_precede(after, before))
+    })
+  }
+
+  /// The original compliance check, which scans every rule. Kept as a
+  /// reference for the rule index.
+  fn complies_with_rules_scan(&self, page_set: &[u32]) -> bool {
     for PageOrderingRule { before, after } in self.rules.iter() {
       if !page_set.contains(before) || !page_set.contains(after) {
         continue;
@@ -76,106 +92,371 @@ impl Input {
   }
 
   fn part_1(&self) -> u32 {
-    let complies_with_rules = |page_set: &[u32]| -> bool {
-      for PageOrderingRule { before, after } in self.rules.iter() {
-        if !page_set.contains(before) || !page_set.contains(after) {
-          continue;
-        }
-
-        let before_index =
-          page_set.iter().position(|&page| page == *before).unwrap();
-        let after_index =
-          page_set.iter().position(|&page| page == *after).unwrap();
-
-        if before_index > after_index {
-          return false;
-        }
-      }
-
-      true
-    };
-
     self
       .page_sets
       .iter()
-      .filter(|page_set| complies_with_rules(page_set))
+      .filter(|page_set| self.complies_with_rules(page_set))
       // get the middle number of each page set
       .map(|page_set| page_set[page_set.len() / 2])
       .sum()
   }
 
-  fn part_2(&self) -> u32 {
-    let non_compliant_page_sets: Vec<_> = self
-      .page_sets
-      .iter()
-      .filter(|page_set| !self.complies_with_rules(page_set))
+  /// Reorders a page set so that it complies with the rules, using Kahn's
+  /// algorithm over the rules that apply to its pages. Pages that the rules
+  /// don't order relative to each other keep their original relative order.
+  fn correct(&self, page_set: &[u32]) -> Result<Vec<u32>, CycleError> {
+    // build the subgraph induced by the page set, by index into the page set
+    let mut successors = vec![Vec::new(); page_set.len()];
+    let mut in_degrees = vec![0; page_set.len()];
+    for (before, &before_page) in page_set.iter().enumerate() {
+      for (after, &after_page) in page_set.iter().enumerate() {
+        if self.index.must_precede(before_page, after_page) {
+          successors[before].push(after);
+          in_degrees[after] += 1;
+        }
+      }
+    }
+
+    // repeatedly take the earliest page that has no unplaced predecessors
+    let mut ready: BTreeSet<usize> = (0..page_set.len())
+      .filter(|&i| in_degrees[i] == 0)
       .collect();
+    let mut corrected = Vec::with_capacity(page_set.len());
+    while let Some(i) = ready.pop_first() {
+      corrected.push(page_set[i]);
+      for &j in &successors[i] {
+        in_degrees[j] -= 1;
+        if in_degrees[j] == 0 {
+          ready.insert(j);
+        }
+      }
+    }
+
+    // any page that was never placed is on, or behind, a cycle
+    if corrected.len() < page_set.len() {
+      return Err(CycleError {
+        pages: (0..page_set.len())
+          .filter(|&i| in_degrees[i] > 0)
+          .map(|i| page_set[i])
+          .collect(),
+      });
+    }
+
+    Ok(corrected)
+  }
+
+  fn part_2(&self) -> Result<u32, CycleError> {
+    let mut output = 0;
+    for page_set in self.page_sets.iter() {
+      if self.complies_with_rules(page_set) {
+        continue;
+      }
+
+      // get the middle number of each corrected page set
+      let page_set = self.correct(page_set)?;
+      output += page_set[page_set.len() / 2];
+    }
 
-    // correct the non-compliant page sets
-    // for each page set, iterate through the rules and swap the numbers if the
-    // rule is violated
+    Ok(output)
+  }
 
-    let corrected_page_sets = non_compliant_page_sets
+  /// Analyzes the rules over every page that any rule mentions.
+  fn analyze_rules(&self) -> RuleAnalysis {
+    let pages: Vec<u32> = self
+      .rules
       .iter()
-      .map(|page_set| {
-        let mut page_set = Vec::clone(page_set);
-        let mut changed = true;
-        while changed {
-          changed = false;
-          for PageOrderingRule { before, after } in self.rules.iter() {
-            if !page_set.contains(before) || !page_set.contains(after) {
-              continue;
-            }
-
-            let before_index =
-              page_set.iter().position(|&page| page == *before).unwrap();
-            let after_index =
-              page_set.iter().position(|&page| page == *after).unwrap();
-
-            if before_index > after_index {
-              page_set.swap(before_index, after_index);
-              changed = true;
-            }
-          }
-        }
+      .flat_map(|rule| [rule.before, rule.after])
+      .collect();
+    RuleAnalysis::new(&self.index, &pages)
+  }
 
-        page_set
+  /// The indices of the non-compliant page sets whose middle page depends on
+  /// which of several valid orders the correction picks.
+  fn ambiguous_page_sets(&self) -> Vec<usize> {
+    self
+      .page_sets
+      .iter()
+      .enumerate()
+      .filter(|(_, page_set)| !self.complies_with_rules(page_set))
+      .filter(|(_, page_set)| {
+        self
+          .middle_pages(page_set)
+          .is_ok_and(|middle_pages| middle_pages.len() > 1)
       })
-      .collect::<Vec<_>>();
+      .map(|(i, _)| i)
+      .collect()
+  }
 
-    // get the middle number of each page set
-    corrected_page_sets
-      .iter()
-      .map(|page_set| page_set[page_set.len() / 2])
-      .sum()
+  /// Computes part 2 by sorting each page set with a comparator derived from
+  /// the rules, which requires the rules to be a strict weak ordering over
+  /// every page set.
+  fn part_2_sorted(&self) -> Result<u32, OrderingError> {
+    let mut output = 0;
+    for page_set in self.page_sets.iter() {
+      let order = PageOrder::for_pages(&self.index, page_set)?;
+      if order.is_sorted(page_set) {
+        continue;
+      }
+
+      let mut page_set = page_set.clone();
+      page_set.sort_by(order.as_fn());
+      output += page_set[page_set.len() / 2];
+    }
+
+    Ok(output)
+  }
+}
+
+/// The rules that apply to a page set contain a cycle, so it can't be
+/// ordered.
+#[derive(Debug, PartialEq, Eq)]
+struct CycleError {
+  /// The pages that could not be placed, in their original order.
+  pages: Vec<u32>,
+}
+
+impl fmt::Display for CycleError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "rules contain a cycle through pages {:?}", self.pages)
+  }
+}
+
+impl std::error::Error for CycleError {}
+
+/// The most pages in a page set that the searches over its orders, like
+/// [`Input::disorder`], can handle. They track the placed pages in a 64-bit
+/// mask.
+const MAX_SEARCH_PAGES: usize = 64;
+
+/// Why a search over the orders of a page set can't run.
+#[derive(Debug, PartialEq, Eq)]
+enum SearchError {
+  Cycle(CycleError),
+  TooManyPages(usize),
+}
+
+impl fmt::Display for SearchError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      SearchError::Cycle(e) => write!(f, "{e}"),
+      SearchError::TooManyPages(pages) => write!(
+        f,
+        "{pages} pages is too many to search, the most is {MAX_SEARCH_PAGES}"
+      ),
+    }
+  }
+}
+
+impl std::error::Error for SearchError {}
+
+impl From<CycleError> for SearchError {
+  fn from(e: CycleError) -> Self { SearchError::Cycle(e) }
+}
+
+/// Prints the problems with the rules as a whole, and the cycles that stop
+/// each uncorrectable update from being corrected.
+fn analyze_rules(input: &Input) {
+  let analysis = input.analyze_rules();
+
+  println!(
+    "{} rules, {}",
+    input.rules.len(),
+    if analysis.is_consistent() {
+      "consistent"
+    } else {
+      "inconsistent"
+    }
+  );
+  for (a, b) in &analysis.contradictions {
+    println!("Contradiction: {a}|{b} and {b}|{a}");
+  }
+  for cycle in &analysis.cycles {
+    println!("Cycle through {} pages: {cycle:?}", cycle.len());
+  }
+  println!(
+    "{} rules are implied by other rules",
+    analysis.redundant.len()
+  );
+  for PageOrderingRule { before, after } in &analysis.redundant {
+    println!("  {before}|{after}");
+  }
+
+  for (i, page_set) in input.page_sets.iter().enumerate() {
+    if input.correct(page_set).is_ok() {
+      continue;
+    }
+    println!("Update {} cannot be corrected: {page_set:?}", i + 1);
+    for cycle in RuleAnalysis::new(&input.index, page_set).cycles {
+      println!("  cycle through pages {cycle:?}");
+    }
+  }
+}
+
+/// Prints the non-compliant updates from most to least disordered, and the
+/// totals over all updates.
+fn print_disorder(input: &Input) {
+  let mut ranked = Vec::new();
+  for (i, page_set) in input.page_sets.iter().enumerate() {
+    match input.disorder(page_set) {
+      Ok(disorder) => ranked.push((i, disorder)),
+      Err(e) => println!("Update {}: {e}", i + 1),
+    }
+  }
+  ranked.sort_by_key(|(i, disorder)| {
+    (std::cmp::Reverse((disorder.swaps, disorder.moves)), *i)
+  });
+
+  for (i, Disorder { swaps, moves }) in &ranked {
+    if *swaps > 0 {
+      println!("Update {}: {swaps} swaps, {moves} moves", i + 1);
+    }
+  }
+  println!(
+    "Total: {} swaps, {} moves",
+    ranked.iter().map(|(_, d)| d.swaps).sum::<usize>(),
+    ranked.iter().map(|(_, d)| d.moves).sum::<usize>()
+  );
+}
+
+/// Prints how many valid orders each non-compliant update has, which pages
+/// can be in the middle, and up to `limit` of the orders themselves.
+fn print_orders(input: &Input, limit: usize) {
+  for (i, page_set) in input.page_sets.iter().enumerate() {
+    if input.complies_with_rules(page_set) {
+      continue;
+    }
+    let ambiguity = match input.ambiguity(page_set) {
+      Ok(ambiguity) => ambiguity,
+      Err(e) => {
+        println!("Update {}: {e}", i + 1);
+        continue;
+      }
+    };
+
+    println!(
+      "Update {}: {} valid orders, middle pages {:?}",
+      i + 1,
+      ambiguity.orders,
+      ambiguity.middle_pages
+    );
+    for order in input.valid_orders(page_set, limit).unwrap() {
+      println!("  {order:?}");
+    }
   }
 }
 
+/// Prints the rule graph in DOT format, or the part of it that applies to the
+/// update with the given number (counting from 1).
+fn dot(input: &Input, args: &[String]) {
+  let page_set = args.first().map(|update| {
+    let update: usize = update.parse().expect("failed to parse update number");
+    input
+      .page_sets
+      .get(update.wrapping_sub(1))
+      .unwrap_or_else(|| panic!("no update number {update}"))
+      .as_slice()
+  });
+  print!("{}", input.to_dot(page_set));
+}
+
 fn main() {
+  let args: Vec<_> = std::env::args().skip(1).collect();
+  if args.first().is_some_and(|arg| arg == "bench") {
+    bench::run(&args[1..]);
+    return;
+  }
+
+  let input_path = PathBuf::from("inputs/day_05.txt");
+
+  // `dot [update]` prints only the rule graph, so that it can be piped to
+  // `dot`
+  if args.first().is_some_and(|arg| arg == "dot") {
+    dot(&Input::from_file(&input_path), &args[1..]);
+    return;
+  }
+
   let now = std::time::Instant::now();
-  let input = Input::from_file(PathBuf::from("inputs/day_05.txt").as_path());
+  let input = Input::from_file(&input_path);
   println!(
     "Parsed input in {:.3}ms",
     now.elapsed().as_secs_f32() * 1000.0
   );
 
+  for line in input.even_length_updates() {
+    println!(
+      "Warning: the update on line {line} has an even number of pages, so its \
+       middle page is the later of the two"
+    );
+  }
+
+  if args.first().is_some_and(|arg| arg == "analyze-rules") {
+    analyze_rules(&input);
+    return;
+  }
+
+  // `--disorder` ranks the updates by how far they are from complying
+  if args.iter().any(|arg| arg == "--disorder") {
+    print_disorder(&input);
+  }
+
+  // `--orders[=N]` counts the valid orders of each non-compliant update, and
+  // lists up to N of them
+  let orders = args.iter().find_map(|arg| match arg.as_str() {
+    "--orders" => Some(0),
+    arg => arg
+      .strip_prefix("--orders=")
+      .map(|n| n.parse().expect("failed to parse number of orders")),
+  });
+  if let Some(limit) = orders {
+    print_orders(&input, limit);
+  }
+
+  // `--explain` shows why each non-compliant update breaks the rules
+  if args.iter().any(|arg| arg == "--explain") {
+    for page_set in &input.page_sets {
+      if let Some(report) = input.explain(page_set) {
+        print!("{report}");
+      }
+    }
+  }
+
   let now = std::time::Instant::now();
   let part_1 = input.part_1();
   println!(
     "Part 1: {part_1}, in {:.3}ms",
     now.elapsed().as_secs_f32() * 1000.0
   );
+  // `--comparator` sorts with a comparator derived from the rules, instead of
+  // correcting with a topological sort
   let now = std::time::Instant::now();
-  let part_2 = input.part_2();
+  let part_2 = if args.iter().any(|arg| arg == "--comparator") {
+    input
+      .part_2_sorted()
+      .unwrap_or_else(|e| panic!("failed to sort page sets: {e}"))
+  } else {
+    input
+      .part_2()
+      .unwrap_or_else(|e| panic!("failed to correct page sets: {e}"))
+  };
   println!(
     "Part 2: {part_2}, in {:.3}ms",
     now.elapsed().as_secs_f32() * 1000.0
   );
+
+  let ambiguous = input.ambiguous_page_sets();
+  if !ambiguous.is_empty() {
+    println!(
+      "Warning: part 2 depends on the correction order, since the rules allow \
+       more than one middle page for updates {:?}",
+      ambiguous.iter().map(|i| i + 1).collect::<Vec<_>>()
+    );
+  }
 }
 
 #[cfg(test)]
 mod tests {
   use super::*;
+  use crate::parse::ParseErrorKind;
 
   const MINI_INPUT: &str = r#"
 47|53
@@ -217,6 +498,338 @@ mod tests {
   #[test]
   fn part_2_example() {
     let input = Input::from_string(MINI_INPUT.to_string());
-    assert_eq!(input.part_2(), 123);
+    assert_eq!(input.part_2(), Ok(123));
+  }
+
+  #[test]
+  fn part_2_comparator_example() {
+    let input = Input::from_string(MINI_INPUT.to_string());
+    assert_eq!(input.part_2_sorted(), Ok(123));
+
+    for page_set in &input.page_sets {
+      let order = PageOrder::for_pages(&input.index, page_set).unwrap();
+      let mut sorted = page_set.clone();
+      sorted.sort_by(order.as_fn());
+      assert!(order.is_sorted(&sorted));
+      assert_eq!(
+        order.is_sorted(page_set),
+        input.complies_with_rules(page_set)
+      );
+      assert_eq!(Ok(sorted), input.correct(page_set));
+    }
+  }
+
+  #[test]
+  fn comparator_requires_strict_weak_ordering() {
+    let input = Input::from_string("1|2\n2|3\n4|5\n5|4\n".to_string());
+    assert!(PageOrder::for_pages(&input.index, &[1, 2]).is_ok());
+    assert_eq!(
+      PageOrder::for_pages(&input.index, &[3, 2, 1]).err(),
+      Some(OrderingError::NotTransitive(1, 2, 3))
+    );
+    assert_eq!(
+      PageOrder::for_pages(&input.index, &[1, 2, 6]).err(),
+      Some(OrderingError::IncomparableNotTransitive(1, 6, 2))
+    );
+    assert_eq!(
+      PageOrder::for_pages(&input.index, &[4, 5]).err(),
+      Some(OrderingError::Contradiction(4, 5))
+    );
+  }
+
+  #[test]
+  fn analyze_example_rules() {
+    let input = Input::from_string(MINI_INPUT.to_string());
+    let analysis = input.analyze_rules();
+    assert!(analysis.is_consistent());
+    assert!(analysis.contradictions.is_empty());
+    // the example rules are a total order on 7 pages: only the 6 rules between
+    // neighbours are needed
+    assert_eq!(input.rules.len() - analysis.redundant.len(), 6);
+  }
+
+  #[test]
+  fn analyze_inconsistent_rules() {
+    let input = Input::from_string(
+      "1|2\n2|1\n2|3\n3|4\n4|2\n5|6\n6|7\n5|7\n".to_string(),
+    );
+    let analysis = input.analyze_rules();
+    assert_eq!(analysis.contradictions, vec![(1, 2)]);
+    assert_eq!(analysis.cycles, vec![vec![1, 2, 3, 4]]);
+    assert_eq!(analysis.redundant, vec![PageOrderingRule {
+      before: 5,
+      after:  7,
+    }]);
+
+    let analysis = RuleAnalysis::new(&input.index, &[3, 4, 2, 6]);
+    assert_eq!(analysis.cycles, vec![vec![2, 3, 4]]);
+    assert!(analysis.contradictions.is_empty());
+
+    // the only way from 1 to 2 is its own rule, even though 3 reaches 2
+    // through 1
+    let input = Input::from_string("1|2\n1|3\n3|1\n".to_string());
+    assert!(input.analyze_rules().redundant.is_empty());
+    // and with a rule from 3 as well, either one of them would do
+    let input = Input::from_string("1|2\n1|3\n3|1\n3|2\n".to_string());
+    assert!(input.analyze_rules().redundant.is_empty());
+    // but a path from the cycle through 4 does imply it
+    let input = Input::from_string("1|2\n1|3\n3|1\n3|4\n4|2\n".to_string());
+    assert_eq!(input.analyze_rules().redundant, vec![PageOrderingRule {
+      before: 1,
+      after:  2,
+    }]);
+  }
+
+  #[test]
+  fn explain_example() {
+    let input = Input::from_string(MINI_INPUT.to_string());
+    assert!(input.explain(&input.page_sets[0]).is_none());
+
+    let report = input.explain(&input.page_sets[5]).unwrap();
+    assert_eq!(report.original, vec![97, 13, 75, 29, 47]);
+    assert_eq!(report.corrected, Ok(vec![97, 75, 47, 29, 13]));
+    assert_eq!(
+      report
+        .violations
+        .iter()
+        .map(|v| (v.rule.before, v.rule.after))
+        .collect::<Vec<_>>(),
+      vec![(75, 13), (29, 13), (47, 13), (47, 29)]
+    );
+    // keeping 97, 75 and 47 in order, only 13 and 29 have to move
+    assert_eq!(report.moves, vec![
+      explain::Move {
+        page:    13,
+        from:    1,
+        ends_at: 4,
+      },
+      explain::Move {
+        page:    29,
+        from:    3,
+        ends_at: 3,
+      },
+    ]);
+    assert!(
+      report
+        .to_string()
+        .contains("  move 29 from index 3 (ends at index 3)\n")
+    );
+  }
+
+  /// Rules that only order pages 1 and 2, and one update of `pages` pages
+  /// that breaks that rule.
+  fn wide_input(pages: u32) -> Input {
+    let update: Vec<_> = [2, 1]
+      .into_iter()
+      .chain(3..=pages)
+      .map(|p| p.to_string())
+      .collect();
+    Input::from_string(format!("1|2\n\n{}\n", update.join(",")))
+  }
+
+  #[test]
+  fn disorder_of_too_many_pages() {
+    let input = wide_input(70);
+    assert_eq!(
+      input.disorder(&input.page_sets[0]),
+      Err(SearchError::TooManyPages(70))
+    );
+    let input = wide_input(5);
+    assert_eq!(
+      input.disorder(&input.page_sets[0]),
+      Ok(Disorder { swaps: 1, moves: 1 })
+    );
+  }
+
+  #[test]
+  fn ambiguity_of_many_pages() {
+    // far too many orders to count, but the middle pages are still found
+    let input = wide_input(70);
+    assert_eq!(
+      input.ambiguity(&input.page_sets[0]),
+      Err(SearchError::TooManyPages(70))
+    );
+    assert_eq!(input.middle_pages(&input.page_sets[0]).unwrap().len(), 70);
+    assert_eq!(input.ambiguous_page_sets(), vec![0]);
+  }
+
+  #[test]
+  fn disorder_example() {
+    let input = Input::from_string(MINI_INPUT.to_string());
+    let disorder: Vec<_> = input
+      .page_sets
+      .iter()
+      .map(|page_set| {
+        let Disorder { swaps, moves } = input.disorder(page_set).unwrap();
+        (swaps, moves)
+      })
+      .collect();
+    assert_eq!(disorder, vec![
+      (0, 0),
+      (0, 0),
+      (0, 0),
+      (1, 1),
+      (1, 1),
+      (4, 2)
+    ]);
+  }
+
+  #[test]
+  fn disorder_with_unordered_pages() {
+    // only 1 must come before 3, but whichever side of 1 or 3 the 2 ends up
+    // on, it has to swap with one of them too
+    let input = Input::from_string("1|3\n".to_string());
+    assert_eq!(
+      input.disorder(&[3, 2, 1]),
+      Ok(Disorder { swaps: 2, moves: 1 })
+    );
+
+    // 1 has to move to the front and 4 to the back, both passing 5 and 6
+    let input = Input::from_string("1|5\n1|6\n5|4\n6|4\n".to_string());
+    assert_eq!(
+      input.disorder(&[4, 5, 6, 1]),
+      Ok(Disorder { swaps: 5, moves: 2 })
+    );
+  }
+
+  #[test]
+  fn example_orders_are_unique() {
+    let input = Input::from_string(MINI_INPUT.to_string());
+    for page_set in &input.page_sets {
+      let ambiguity = input.ambiguity(page_set).unwrap();
+      let corrected = input.correct(page_set).unwrap();
+      assert_eq!(ambiguity.orders, 1);
+      assert_eq!(ambiguity.middle_pages, vec![corrected[corrected.len() / 2]]);
+      assert_eq!(input.valid_orders(page_set, 10), Ok(vec![corrected]));
+    }
+    assert!(input.ambiguous_page_sets().is_empty());
+  }
+
+  #[test]
+  fn ambiguous_orders() {
+    // 1 comes first and 5 last, but 2, 3 and 4 can be in any order
+    let input = Input::from_string(
+      "1|2\n1|3\n1|4\n2|5\n3|5\n4|5\n\n5,4,3,2,1\n".to_string(),
+    );
+    assert_eq!(
+      input.ambiguity(&[5, 4, 3, 2, 1]),
+      Ok(ambiguity::Ambiguity {
+        orders:       6,
+        middle_pages: vec![4, 3, 2],
+      })
+    );
+    assert_eq!(
+      input.valid_orders(&[5, 4, 3, 2, 1], 2),
+      Ok(vec![vec![1, 4, 3, 2, 5], vec![1, 4, 2, 3, 5]])
+    );
+    assert_eq!(input.ambiguous_page_sets(), vec![0]);
+
+    // with 2 before 3 and 4, only 3 or 4 can be in the middle
+    let input = Input::from_string("1|2\n2|3\n2|4\n3|5\n4|5\n".to_string());
+    assert_eq!(
+      input.ambiguity(&[1, 2, 3, 4, 5]).unwrap().middle_pages,
+      vec![3, 4]
+    );
+  }
+
+  #[test]
+  fn dot_export() {
+    const RULE_GRAPH: &str = r#"
+digraph rules {
+  node [shape=circle];
+  1 -> 2;
+  1 -> 3;
+  2 -> 3;
+  3 -> 4;
+}
+"#;
+    const UPDATE_GRAPH: &str = r##"
+digraph rules {
+  node [shape=circle];
+  3 [xlabel="#0"];
+  1 [xlabel="#1"];
+  2 [xlabel="#2"];
+  1 -> 2;
+  1 -> 3 [color=red, penwidth=2];
+  2 -> 3 [color=red, penwidth=2];
+}
+"##;
+
+    let input = Input::from_string("1|2\n2|3\n1|3\n3|4\n".to_string());
+    assert_eq!(input.to_dot(None), RULE_GRAPH.trim_start());
+    assert_eq!(input.to_dot(Some(&[3, 1, 2])), UPDATE_GRAPH.trim_start());
+  }
+
+  #[test]
+  fn rule_index_matches_scan() {
+    let input = bench::synthetic_input(&bench::Config {
+      pages:      60,
+      rules:      400,
+      updates:    200,
+      update_len: 9,
+      seed:       5,
+    });
+
+    let compliant = input
+      .page_sets
+      .iter()
+      .filter(|page_set| input.complies_with_rules(page_set))
+      .count();
+    assert!(compliant > 0 && compliant < input.page_sets.len());
+
+    for page_set in &input.page_sets {
+      assert_eq!(
+        input.complies_with_rules(page_set),
+        input.complies_with_rules_scan(page_set),
+        "{page_set:?}"
+      );
+    }
+  }
+
+  #[test]
+  fn parse_errors() {
+    let error =
+      |content: &str| Input::parse(content).err().map(|e| (e.line, e.kind));
+
+    assert_eq!(
+      error("1|2\n3,4\n\n1,2\n"),
+      Some((2, ParseErrorKind::InvalidRule("3,4".into())))
+    );
+    assert_eq!(
+      error("1|2\n\n1,2\n2|3\n"),
+      Some((4, ParseErrorKind::RuleInUpdates))
+    );
+    assert_eq!(
+      error("1|2\n\n1,2\n\n2,1\n"),
+      Some((4, ParseErrorKind::BlankLineInUpdates))
+    );
+    assert_eq!(
+      error("1|2\n\n1,2,x\n"),
+      Some((3, ParseErrorKind::InvalidUpdate("1,2,x".into())))
+    );
+    assert_eq!(
+      error("1|2\n\n1,2,1\n"),
+      Some((3, ParseErrorKind::DuplicatePage(1)))
+    );
+    // the blank lines before the updates can't be told apart from blank
+    // lines before the rules, so this is updates without any rules
+    assert_eq!(error("\n\n1,2\n"), Some((3, ParseErrorKind::MissingRules)));
+
+    // windows line endings and surrounding blank lines are fine
+    let input = Input::parse("\r\n1|2\r\n\r\n1,2\r\n3,2,1\r\n\r\n").unwrap();
+    assert_eq!(input.page_sets, vec![vec![1, 2], vec![3, 2, 1]]);
+    assert_eq!(input.even_length_updates(), vec![4]);
+  }
+
+  #[test]
+  fn cyclic_rules() {
+    let input = Input::from_string("1|2\n2|3\n3|1\n\n4,3,2,1\n".to_string());
+    assert_eq!(
+      input.correct(&[4, 3, 2, 1]),
+      Err(CycleError {
+        pages: vec![3, 2, 1],
+      })
+    );
+    assert!(input.part_2().is_err());
   }
 }
@@ -0,0 +1,121 @@
+use std::{cmp::Ordering, fmt};
+
+use crate::rules::RuleIndex;
+
+/// Compares pages by the rules: a page is less than another if a rule says it
+/// must come before it, and pages that no rule relates compare equal.
+///
+/// This is only a valid comparator for `sort_by` over a set of pages on which
+/// the rules form a strict weak ordering, which [`PageOrder::for_pages`]
+/// checks.
+#[derive(Clone, Copy)]
+pub struct PageOrder<'a> {
+  index: &'a RuleIndex,
+}
+
+/// Why the rules are not a strict weak ordering over a set of pages.
+#[derive(Debug, PartialEq, Eq)]
+pub enum OrderingError {
+  /// A page must come before itself.
+  Reflexive(u32),
+  /// Each page must come before the other.
+  Contradiction(u32, u32),
+  /// The first page must come before the second and the second before the
+  /// third, but nothing orders the first and third.
+  NotTransitive(u32, u32, u32),
+  /// The first and third pages are ordered, but the second is ordered
+  /// relative to neither.
+  IncomparableNotTransitive(u32, u32, u32),
+}
+
+impl fmt::Display for OrderingError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      OrderingError::Reflexive(a) => {
+        write!(f, "page {a} must come before itself")
+      }
+      OrderingError::Contradiction(a, b) => {
+        write!(f, "pages {a} and {b} must each come before the other")
+      }
+      OrderingError::NotTransitive(a, b, c) => write!(
+        f,
+        "{a} must come before {b} and {b} before {c}, but no rule orders {a} \
+         and {c}"
+      ),
+      OrderingError::IncomparableNotTransitive(a, b, c) => write!(
+        f,
+        "{a} and {c} are ordered, but {b} is ordered relative to neither"
+      ),
+    }
+  }
+}
+
+impl std::error::Error for OrderingError {}
+
+impl<'a> PageOrder<'a> {
+  /// Builds a comparator that is known to be a strict weak ordering over
+  /// `pages`.
+  pub fn for_pages(
+    index: &'a RuleIndex,
+    pages: &[u32],
+  ) -> Result<Self, OrderingError> {
+    let order = Self { index };
+
+    for &a in pages {
+      if index.must_precede(a, a) {
+        return Err(OrderingError::Reflexive(a));
+      }
+      for &b in pages {
+        if a != b && index.must_precede(a, b) && index.must_precede(b, a) {
+          return Err(OrderingError::Contradiction(a, b));
+        }
+      }
+    }
+
+    // both "less than" and "equal to" must be transitive
+    for &a in pages {
+      for &b in pages {
+        for &c in pages {
+          let ab = order.compare(&a, &b);
+          let bc = order.compare(&b, &c);
+          let ac = order.compare(&a, &c);
+          if ab == Ordering::Less
+            && bc == Ordering::Less
+            && ac != Ordering::Less
+          {
+            return Err(OrderingError::NotTransitive(a, b, c));
+          }
+          if ab == Ordering::Equal
+            && bc == Ordering::Equal
+            && ac != Ordering::Equal
+          {
+            return Err(OrderingError::IncomparableNotTransitive(a, b, c));
+          }
+        }
+      }
+    }
+
+    Ok(order)
+  }
+
+  pub fn compare(&self, a: &u32, b: &u32) -> Ordering {
+    if self.index.must_precede(*a, *b) {
+      Ordering::Less
+    } else if self.index.must_precede(*b, *a) {
+      Ordering::Greater
+    } else {
+      Ordering::Equal
+    }
+  }
+
+  /// The comparator as a closure, for `sort_by` and friends.
+  pub fn as_fn(&self) -> impl Fn(&u32, &u32) -> Ordering + 'a {
+    let order = *self;
+    move |a, b| order.compare(a, b)
+  }
+
+  /// Whether `pages` is in order, for `is_sorted_by`.
+  pub fn is_sorted(&self, pages: &[u32]) -> bool {
+    pages.is_sorted_by(|a, b| self.compare(a, b) != Ordering::Greater)
+  }
+}
@@ -0,0 +1,153 @@
+use std::{collections::HashSet, fmt};
+
+use crate::{Input, PageOrderingRule, rules::RuleIndex};
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ParseError {
+  /// The 1-based line number of the offending line.
+  pub line: usize,
+  pub kind: ParseErrorKind,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ParseErrorKind {
+  /// The input starts with an update rather than a rule. Every input needs
+  /// rules, since without them there is nothing to check the updates
+  /// against.
+  MissingRules,
+  /// A line in the rules section isn't of the form `a|b`.
+  InvalidRule(String),
+  /// A line in the updates section isn't of the form `a,b,...`.
+  InvalidUpdate(String),
+  /// A rule appears after the blank line that ends the rules section.
+  RuleInUpdates,
+  /// A page appears twice in the same update.
+  DuplicatePage(u32),
+  /// A blank line appears between two updates.
+  BlankLineInUpdates,
+}
+
+impl fmt::Display for ParseError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "line {}: ", self.line)?;
+    match &self.kind {
+      ParseErrorKind::MissingRules => {
+        write!(f, "found an update before any rules")
+      }
+      ParseErrorKind::InvalidRule(line) => {
+        write!(f, "expected a rule like \"47|53\", found {line:?}")
+      }
+      ParseErrorKind::InvalidUpdate(line) => {
+        write!(f, "expected an update like \"75,47,61\", found {line:?}")
+      }
+      ParseErrorKind::RuleInUpdates => {
+        write!(f, "found a rule after the end of the rules section")
+      }
+      ParseErrorKind::DuplicatePage(page) => {
+        write!(f, "page {page} appears more than once in the update")
+      }
+      ParseErrorKind::BlankLineInUpdates => {
+        write!(f, "found a blank line between updates")
+      }
+    }
+  }
+}
+
+impl std::error::Error for ParseError {}
+
+enum Section {
+  Rules,
+  Separator,
+  Updates,
+}
+
+impl Input {
+  /// Parses the rules section, a blank line, and the updates section. Blank
+  /// lines before the rules and after the updates are ignored, and the
+  /// updates section may be left out entirely, but the rules section can't.
+  pub fn parse(content: &str) -> Result<Self, ParseError> {
+    let mut rules = HashSet::new();
+    let mut page_sets = Vec::new();
+    let mut update_lines = Vec::new();
+
+    let mut section = Section::Rules;
+    let mut blank_line = None;
+    for (i, line) in content.lines().enumerate() {
+      let line_number = i + 1;
+      let line = line.trim_end();
+      let error = |kind| ParseError {
+        line: line_number,
+        kind,
+      };
+
+      if line.is_empty() {
+        match section {
+          Section::Rules if !rules.is_empty() => section = Section::Separator,
+          Section::Updates => blank_line = blank_line.or(Some(line_number)),
+          _ => (),
+        }
+        continue;
+      }
+
+      if let Section::Separator = section {
+        section = Section::Updates;
+      }
+      match section {
+        Section::Rules if rules.is_empty() && parse_update(line).is_some() => {
+          return Err(error(ParseErrorKind::MissingRules));
+        }
+        Section::Rules | Section::Separator => {
+          rules
+            .insert(parse_rule(line).ok_or_else(|| {
+              error(ParseErrorKind::InvalidRule(line.into()))
+            })?);
+        }
+        Section::Updates => {
+          // a blank line is only allowed after the last update
+          if let Some(line) = blank_line {
+            return Err(ParseError {
+              line,
+              kind: ParseErrorKind::BlankLineInUpdates,
+            });
+          }
+          if parse_rule(line).is_some() {
+            return Err(error(ParseErrorKind::RuleInUpdates));
+          }
+
+          let page_set = parse_update(line)
+            .ok_or_else(|| error(ParseErrorKind::InvalidUpdate(line.into())))?;
+          let mut seen = HashSet::new();
+          if let Some(&page) = page_set.iter().find(|&&page| !seen.insert(page))
+          {
+            return Err(error(ParseErrorKind::DuplicatePage(page)));
+          }
+
+          page_sets.push(page_set);
+          update_lines.push(line_number);
+        }
+      }
+    }
+
+    Ok(Self {
+      index: RuleIndex::new(&rules),
+      rules,
+      page_sets,
+      update_lines,
+    })
+  }
+}
+
+fn parse_rule(line: &str) -> Option<PageOrderingRule> {
+  let (before, after) = line.split_once('|')?;
+  Some(PageOrderingRule {
+    before: before.trim().parse().ok()?,
+    after:  after.trim().parse().ok()?,
+  })
+}
+
+fn parse_update(line: &str) -> Option<Vec<u32>> {
+  line
+    .split(',')
+    .map(|page| page.trim().parse().ok())
+    .collect()
+}
@@ -0,0 +1,53 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::PageOrderingRule;
+
+/// The page ordering rules, indexed by the page that must come first.
+#[derive(Debug, Default)]
+pub struct RuleIndex {
+  successors: HashMap<u32, HashSet<u32>>,
+}
+
+impl RuleIndex {
+  pub fn new<'a>(
+    rules: impl IntoIterator<Item = &'a PageOrderingRule>,
+  ) -> Self {
+    let mut successors: HashMap<u32, HashSet<u32>> = HashMap::new();
+    for PageOrderingRule { before, after } in rules {
+      successors.entry(*before).or_default().insert(*after);
+    }
+    Self { successors }
+  }
+
+  /// Whether a rule says that `before` must come before `after`.
+  pub fn must_precede(&self, before: u32, after: u32) -> bool {
+    self
+      .successors
+      .get(&before)
+      .is_some_and(|successors| successors.contains(&after))
+  }
+
+  /// The pages that some rule says must come after `page`.
+  pub fn successors(&self, page: u32) -> impl Iterator<Item = u32> + '_ {
+    self.successors.get(&page).into_iter().flatten().copied()
+  }
+
+  /// Returns a matrix where `[i][j]` is set if `pages[i]` must come before
+  /// `pages[j]`, directly or through other pages in `pages`.
+  pub fn closure(&self, pages: &[u32]) -> Vec<Vec<bool>> {
+    let mut precedes: Vec<Vec<bool>> = pages
+      .iter()
+      .map(|&a| pages.iter().map(|&b| self.must_precede(a, b)).collect())
+      .collect();
+
+    let n = pages.len();
+    for k in 0..n {
+      for i in 0..n {
+        for j in 0..n {
+          precedes[i][j] |= precedes[i][k] && precedes[k][j];
+        }
+      }
+    }
+    precedes
+  }
+}
@@ -0,0 +1,111 @@
+use direction::Direction8;
+
+use crate::{
+  grid::Grid,
+  incremental::{ObstacleError, check_obstacle},
+  rules::MovementRules,
+  sim::{GuardSim, GuardState, Outcome},
+};
+
+/// Replays the guard's walk, calling `emit` with each frame as text. There
+/// is a frame for every step, followed by one with the outcome. With an
+/// `obstacle`, it is drawn as an `O`, and if the guard ends up looping, the
+/// final frame draws the loop with `|`, `-` and `+` like the puzzle text,
+/// and `/` and `\\` for diagonal moves.
+///
+/// The obstacle must be on the grid, in a free cell other than the guard's.
+pub fn animate(
+  grid: &Grid,
+  start: GuardState,
+  rules: MovementRules,
+  obstacle: Option<(usize, usize)>,
+  mut emit: impl FnMut(&str),
+) -> Result<(), ObstacleError> {
+  let mut grid = grid.clone();
+  if let Some(pos) = obstacle {
+    check_obstacle(&grid, start.pos, pos)?;
+    if grid.get(pos.0, pos.1) {
+      return Err(ObstacleError::AlreadyBlocked(pos));
+    }
+    grid.set(pos.0, pos.1, true);
+  }
+
+  let mut trail = Grid::new(grid.width(), grid.height());
+  let mut states = Vec::new();
+  let mut sim = GuardSim::with_rules(&grid, start, rules);
+  for (step, state) in sim.by_ref().enumerate() {
+    trail.set(state.pos.0, state.pos.1, true);
+    states.push(state);
+
+    let mut frame = draw(&grid, obstacle, |x, y| {
+      if (x, y) == state.pos {
+        Some(state.dir.glyph())
+      } else {
+        trail.get(x, y).then_some('X')
+      }
+    });
+    frame.push_str(&format!("step {step}\n"));
+    emit(&frame);
+  }
+
+  let frame = match sim.outcome() {
+    Some(Outcome::Loop) => {
+      let entry = sim.loop_start().expect("walk did not loop");
+      let first = states.iter().position(|&s| s == entry).unwrap();
+      let lap = &states[first..];
+
+      let mut frame = draw(&grid, obstacle, |x, y| {
+        // a cell crossed in more than one line is a corner
+        lap
+          .iter()
+          .filter(|s| s.pos == (x, y))
+          .map(|s| match s.dir {
+            Direction8::Up | Direction8::Down => '|',
+            Direction8::Left | Direction8::Right => '-',
+            dir => dir.glyph(),
+          })
+          .reduce(|a, b| if a == b { a } else { '+' })
+      });
+      frame.push_str(&format!(
+        "guard loops after {first} steps, {} steps per lap\n",
+        lap.len()
+      ));
+      frame
+    }
+    outcome => {
+      let mut frame =
+        draw(&grid, obstacle, |x, y| trail.get(x, y).then_some('X'));
+      let verb = match outcome {
+        Some(Outcome::Stop) => "stops",
+        _ => "leaves",
+      };
+      frame.push_str(&format!("guard {verb} after {} steps\n", states.len()));
+      frame
+    }
+  };
+  emit(&frame);
+  Ok(())
+}
+
+/// Draws the grid, with `overlay` deciding what goes in each free cell.
+fn draw(
+  grid: &Grid,
+  obstacle: Option<(usize, usize)>,
+  overlay: impl Fn(usize, usize) -> Option<char>,
+) -> String {
+  let mut out = String::new();
+  for y in 0..grid.height() {
+    for x in 0..grid.width() {
+      let c = if obstacle == Some((x, y)) {
+        'O'
+      } else if grid.get(x, y) {
+        '#'
+      } else {
+        overlay(x, y).unwrap_or('.')
+      };
+      out.push(c);
+    }
+    out.push('\n');
+  }
+  out
+}
@@ -0,0 +1,131 @@
+use std::{collections::HashSet, mem::size_of, str::FromStr};
+
+use direction::Direction4;
+
+use crate::{jump::JumpTable, sim::GuardState};
+
+/// How to tell that a guard's walk has become a loop. Each one follows the
+/// walk from turn to turn with the jump table.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LoopDetector {
+  /// Mark every state reached in a grid-sized table.
+  #[default]
+  Visited,
+  /// Keep a set of only the states reached, which are all turns.
+  TurnsOnly,
+  /// Brent's algorithm, which keeps just two states, at the cost of walking
+  /// part of the loop again.
+  Brent,
+}
+
+impl FromStr for LoopDetector {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "visited" => Ok(LoopDetector::Visited),
+      "turns" => Ok(LoopDetector::TurnsOnly),
+      "brent" => Ok(LoopDetector::Brent),
+      s => Err(format!(
+        "unknown loop detector {s:?}, expected visited, turns or brent"
+      )),
+    }
+  }
+}
+
+/// The result of a loop check.
+#[derive(Clone, Copy, Debug)]
+pub struct LoopCheck {
+  pub looped: bool,
+  /// How many bytes the detector used to remember states.
+  pub memory: usize,
+}
+
+impl JumpTable {
+  /// Checks whether a guard starting at `start` loops with an extra obstacle
+  /// at `obstacle`, using the given detector.
+  pub fn check_loop(
+    &self,
+    start: GuardState<Direction4>,
+    obstacle: (usize, usize),
+    detector: LoopDetector,
+  ) -> LoopCheck {
+    let next = |state| self.next_turn(state, obstacle);
+
+    match detector {
+      LoopDetector::Visited => {
+        let mut visited = vec![[false; 4]; self.width() * self.height()];
+
+        let mut state = Some(start);
+        let looped = loop {
+          let Some(GuardState { pos, dir }) = state else {
+            break false;
+          };
+          let seen = &mut visited[pos.1 * self.width() + pos.0][dir.as_usize()];
+          if *seen {
+            break true;
+          }
+          *seen = true;
+          state = next(GuardState { pos, dir });
+        };
+        LoopCheck {
+          looped,
+          memory: visited.len() * size_of::<[bool; 4]>(),
+        }
+      }
+      LoopDetector::TurnsOnly => {
+        let mut turns = HashSet::new();
+
+        let mut state = Some(start);
+        let looped = loop {
+          let Some(s) = state else {
+            break false;
+          };
+          if !turns.insert(s) {
+            break true;
+          }
+          state = next(s);
+        };
+        LoopCheck {
+          looped,
+          memory: turns.capacity() * size_of::<GuardState<Direction4>>(),
+        }
+      }
+      LoopDetector::Brent => LoopCheck {
+        looped: brent(start, next),
+        memory: 2 * size_of::<GuardState<Direction4>>(),
+      },
+    }
+  }
+}
+
+/// Brent's cycle detection over the sequence `start, next(start), ...`,
+/// which ends when `next` returns `None`. The hare runs ahead, and the
+/// tortoise teleports to it at every power of two, so the hare meets it
+/// once the power is at least the length of the cycle.
+fn brent(
+  start: GuardState<Direction4>,
+  next: impl Fn(GuardState<Direction4>) -> Option<GuardState<Direction4>>,
+) -> bool {
+  let mut power = 1;
+  let mut length = 1;
+  let mut tortoise = start;
+  let Some(mut hare) = next(start) else {
+    return false;
+  };
+
+  while tortoise != hare {
+    if power == length {
+      tortoise = hare;
+      power *= 2;
+      length = 0;
+    }
+    let Some(next_hare) = next(hare) else {
+      return false;
+    };
+    hare = next_hare;
+    length += 1;
+  }
+
+  true
+}
@@ -0,0 +1,208 @@
+use std::fmt;
+
+use direction::Direction4;
+use rand::{Rng, SeedableRng, rngs::StdRng};
+
+use crate::{
+  Input,
+  grid::Grid,
+  sim::{GuardSim, GuardState, Outcome},
+};
+
+/// How many cells to change, one at a time, while looking for a map with the
+/// wanted number of loop positions, before starting again from a new map.
+const SEARCH_STEPS: usize = 200;
+
+/// How many random maps to try before giving up.
+const MAP_ATTEMPTS: usize = 1000;
+
+/// What a generated map is guaranteed to have.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Guarantee {
+  /// Nothing: the guard may loop, in which case part 1 has no answer.
+  #[default]
+  Nothing,
+  /// The guard walks off the grid.
+  Exits,
+  /// The guard walks off the grid, and exactly this many obstacle positions
+  /// make it loop instead.
+  LoopPositions(usize),
+}
+
+pub struct Config {
+  pub width:     usize,
+  pub height:    usize,
+  /// The chance of each cell holding an obstacle.
+  pub density:   f64,
+  pub seed:      u64,
+  pub guarantee: Guarantee,
+}
+
+/// A generated map, along with its expected answers.
+pub struct Map {
+  pub text:   String,
+  /// The part 1 answer, or `None` if the guard loops.
+  pub part_1: Option<usize>,
+  /// The part 2 answer, from the brute-force solver, or `None` if the guard
+  /// loops.
+  pub part_2: Option<usize>,
+}
+
+/// Why no map was generated.
+#[derive(Debug, PartialEq)]
+pub enum GenerateError {
+  /// The grid has room for fewer than two cells, the guard's and one more.
+  TooSmall,
+  /// The density isn't a probability, from 0 to 1.
+  Density(f64),
+  /// The guard looped on every map tried.
+  NoExit,
+  /// No map tried had the wanted number of loop positions, and the closest
+  /// had this many.
+  LoopPositions { wanted: usize, closest: usize },
+}
+
+impl fmt::Display for GenerateError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      GenerateError::TooSmall => write!(f, "the grid is too small for a guard"),
+      GenerateError::Density(density) => {
+        write!(f, "density {density} is not between 0 and 1")
+      }
+      GenerateError::NoExit => write!(
+        f,
+        "the guard looped on each of {MAP_ATTEMPTS} maps, try a lower density"
+      ),
+      GenerateError::LoopPositions { wanted, closest } => write!(
+        f,
+        "wanted {wanted} loop positions, but got no closer than {closest} in \
+         {MAP_ATTEMPTS} maps"
+      ),
+    }
+  }
+}
+
+impl std::error::Error for GenerateError {}
+
+impl Config {
+  pub fn generate(&self) -> Result<Map, GenerateError> {
+    if self.width * self.height < 2 {
+      return Err(GenerateError::TooSmall);
+    }
+    if !(0.0..=1.0).contains(&self.density) {
+      return Err(GenerateError::Density(self.density));
+    }
+
+    let mut rng = StdRng::seed_from_u64(self.seed);
+    let mut input = self.random_input(&mut rng);
+    let mut closest = None;
+
+    for attempt in 1.. {
+      let found = match self.guarantee {
+        Guarantee::Nothing => true,
+        Guarantee::Exits => exits(&input),
+        Guarantee::LoopPositions(wanted) => {
+          exits(&input) && {
+            let count = self.search(&mut input, wanted, &mut rng);
+            closest = closest
+              .into_iter()
+              .chain([count])
+              .min_by_key(|c: &usize| c.abs_diff(wanted));
+            count == wanted
+          }
+        }
+      };
+      if found {
+        break;
+      }
+
+      if attempt == MAP_ATTEMPTS {
+        return Err(match (self.guarantee, closest) {
+          (Guarantee::LoopPositions(wanted), Some(closest)) => {
+            GenerateError::LoopPositions { wanted, closest }
+          }
+          _ => GenerateError::NoExit,
+        });
+      }
+      input = self.random_input(&mut rng);
+    }
+
+    let (part_1, part_2) = if exits(&input) {
+      (Some(input.part_1()), Some(input.part_2_brute_force()))
+    } else {
+      (None, None)
+    };
+    if let Guarantee::LoopPositions(wanted) = self.guarantee {
+      assert_eq!(part_2, Some(wanted), "brute force disagrees with search");
+    }
+    Ok(Map {
+      text: input.render_loop_obstacles(&[]),
+      part_1,
+      part_2,
+    })
+  }
+
+  fn random_input(&self, rng: &mut StdRng) -> Input {
+    let mut grid = Grid::new(self.width, self.height);
+    for y in 0..self.height {
+      for x in 0..self.width {
+        grid.set(x, y, rng.gen_bool(self.density));
+      }
+    }
+
+    let pos = (rng.gen_range(0..self.width), rng.gen_range(0..self.height));
+    grid.set(pos.0, pos.1, false);
+    let dir = Direction4::ALL[rng.gen_range(0..4)].into();
+
+    Input {
+      grid,
+      guards: vec![GuardState { pos, dir }],
+    }
+  }
+
+  /// Adds or removes obstacles in random cells, keeping each change that
+  /// leaves the guard exiting and doesn't move the number of loop positions
+  /// further from `wanted`. Returns the number it got to, which is `wanted`
+  /// if the search succeeded.
+  ///
+  /// This counts with the fast part 2 solver, which is checked against the
+  /// brute-force one on the finished map.
+  fn search(
+    &self,
+    input: &mut Input,
+    wanted: usize,
+    rng: &mut StdRng,
+  ) -> usize {
+    let mut count = input.part_2();
+    let start = input.start().pos;
+
+    for _ in 0..SEARCH_STEPS {
+      if count == wanted {
+        break;
+      }
+
+      let (x, y) =
+        (rng.gen_range(0..self.width), rng.gen_range(0..self.height));
+      if (x, y) == start {
+        continue;
+      }
+      input.grid.set(x, y, !input.grid.get(x, y));
+
+      let new_count = exits(input).then(|| input.part_2());
+      match new_count {
+        Some(new_count)
+          if new_count.abs_diff(wanted) <= count.abs_diff(wanted) =>
+        {
+          count = new_count;
+        }
+        _ => input.grid.set(x, y, !input.grid.get(x, y)),
+      }
+    }
+
+    count
+  }
+}
+
+fn exits(input: &Input) -> bool {
+  GuardSim::new(&input.grid, input.start()).run() == Outcome::Exit
+}
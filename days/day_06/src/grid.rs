@@ -28,7 +28,7 @@ impl Grid {
   }
   pub fn width(&self) -> usize { self.width }
   pub fn height(&self) -> usize { self.height }
-  pub fn iter(&self) -> GridIter {
+  pub fn iter(&self) -> GridIter<'_> {
     GridIter {
       grid: self,
       x:    0,
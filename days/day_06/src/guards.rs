@@ -0,0 +1,143 @@
+use std::collections::HashSet;
+
+use crate::{
+  grid::Grid,
+  rules::MovementRules,
+  sim::{GuardSim, GuardState, Outcome},
+};
+
+/// Two guards meeting during a walk.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Collision {
+  pub step:   usize,
+  /// The indices of the two guards, lower first.
+  pub guards: (usize, usize),
+  /// Where they met. If they swapped cells, this is where the first guard
+  /// ended up.
+  pub pos:    (usize, usize),
+  /// Whether they passed through each other rather than sharing a cell.
+  pub swap:   bool,
+}
+
+/// One guard's part in a [`GuardsReport`].
+#[derive(Debug, PartialEq, Eq)]
+pub struct GuardWalk {
+  pub start:   GuardState,
+  pub outcome: Outcome,
+  /// How many distinct cells the guard visited.
+  pub visited: usize,
+}
+
+/// The result of walking several guards through the same grid.
+#[derive(Debug, PartialEq, Eq)]
+pub struct GuardsReport {
+  pub walks:      Vec<GuardWalk>,
+  pub collisions: Vec<Collision>,
+  /// How many distinct cells any guard visited.
+  pub coverage:   usize,
+}
+
+/// Walks every guard in lockstep, one step each per tick, and reports where
+/// they run into each other.
+///
+/// Guards don't get in each other's way: each one walks as if it were alone,
+/// so a collision is only recorded. A guard that leaves the grid is gone, one
+/// that stops stays where it stopped, and one that loops keeps walking its
+/// loop. The walk ends when every guard has left, stopped or been once around
+/// its loop, so collisions between looping guards after that aren't found.
+pub fn walk_guards(
+  grid: &Grid,
+  guards: &[GuardState],
+  rules: MovementRules,
+) -> GuardsReport {
+  struct Walk {
+    states:     Vec<GuardState>,
+    outcome:    Outcome,
+    loop_start: usize,
+  }
+
+  let walks: Vec<_> = guards
+    .iter()
+    .map(|&start| {
+      let mut sim = GuardSim::with_rules(grid, start, rules);
+      let states: Vec<_> = sim.by_ref().collect();
+      let outcome = sim.outcome().expect("walk ended without an outcome");
+      let loop_start = sim
+        .loop_start()
+        .map_or(0, |entry| states.iter().position(|&s| s == entry).unwrap());
+      Walk {
+        states,
+        outcome,
+        loop_start,
+      }
+    })
+    .collect();
+
+  // where a guard is after `step` steps, if it is still on the grid
+  let pos_at = |walk: &Walk, step: usize| -> Option<(usize, usize)> {
+    if let Some(state) = walk.states.get(step) {
+      return Some(state.pos);
+    }
+    match walk.outcome {
+      Outcome::Exit => None,
+      Outcome::Stop => walk.states.last().map(|s| s.pos),
+      Outcome::Loop => {
+        let lap = walk.states.len() - walk.loop_start;
+        Some(walk.states[walk.loop_start + (step - walk.loop_start) % lap].pos)
+      }
+    }
+  };
+
+  let ticks = walks.iter().map(|w| w.states.len()).max().unwrap_or(0);
+  let mut collisions = Vec::new();
+  for step in 1..ticks {
+    for a in 0..walks.len() {
+      for b in a + 1..walks.len() {
+        let (Some(now_a), Some(now_b)) =
+          (pos_at(&walks[a], step), pos_at(&walks[b], step))
+        else {
+          continue;
+        };
+        let before_a = pos_at(&walks[a], step - 1);
+        let before_b = pos_at(&walks[b], step - 1);
+
+        if now_a == now_b {
+          collisions.push(Collision {
+            step,
+            guards: (a, b),
+            pos: now_a,
+            swap: false,
+          });
+        } else if before_a == Some(now_b) && before_b == Some(now_a) {
+          collisions.push(Collision {
+            step,
+            guards: (a, b),
+            pos: now_a,
+            swap: true,
+          });
+        }
+      }
+    }
+  }
+
+  let mut covered = HashSet::new();
+  let walks = guards
+    .iter()
+    .zip(walks)
+    .map(|(&start, walk)| {
+      let cells: HashSet<_> = walk.states.iter().map(|s| s.pos).collect();
+      covered.extend(cells.iter().copied());
+      GuardWalk {
+        start,
+        outcome: walk.outcome,
+        visited: cells.len(),
+      }
+    })
+    .collect();
+
+  GuardsReport {
+    walks,
+    collisions,
+    coverage: covered.len(),
+  }
+}
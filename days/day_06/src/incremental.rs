@@ -0,0 +1,231 @@
+use std::{collections::BTreeSet, fmt};
+
+use crate::{
+  grid::Grid,
+  rules::MovementRules,
+  sim::{GuardState, Outcome, step, target},
+};
+
+/// Why an obstacle can't be added or removed.
+#[derive(Debug, PartialEq, Eq)]
+pub enum ObstacleError {
+  OutOfBounds((usize, usize)),
+  OnGuard((usize, usize)),
+  AlreadyBlocked((usize, usize)),
+  NotBlocked((usize, usize)),
+}
+
+impl fmt::Display for ObstacleError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      ObstacleError::OutOfBounds(pos) => write!(f, "{pos:?} is off the grid"),
+      ObstacleError::OnGuard(pos) => {
+        write!(f, "{pos:?} is where the guard starts")
+      }
+      ObstacleError::AlreadyBlocked(pos) => {
+        write!(f, "there is already an obstacle at {pos:?}")
+      }
+      ObstacleError::NotBlocked(pos) => {
+        write!(f, "there is no obstacle at {pos:?}")
+      }
+    }
+  }
+}
+
+impl std::error::Error for ObstacleError {}
+
+/// Checks that an obstacle at `pos` would be on the grid, and not where the
+/// guard starts.
+pub fn check_obstacle(
+  grid: &Grid,
+  start: (usize, usize),
+  pos: (usize, usize),
+) -> Result<(), ObstacleError> {
+  if pos.0 >= grid.width() || pos.1 >= grid.height() {
+    return Err(ObstacleError::OutOfBounds(pos));
+  }
+  if pos == start {
+    return Err(ObstacleError::OnGuard(pos));
+  }
+  Ok(())
+}
+
+/// How the walk changed after adding or removing an obstacle.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Change {
+  /// How many of the walk's steps were kept. The rest were walked again.
+  pub kept:    usize,
+  /// Cells that the guard visits now, but didn't before.
+  pub added:   BTreeSet<(usize, usize)>,
+  /// Cells that the guard visited before, but doesn't now.
+  pub removed: BTreeSet<(usize, usize)>,
+}
+
+/// A guard's walk that is kept up to date as obstacles are added and
+/// removed.
+///
+/// The walk only depends on an obstacle from the first step where the guard
+/// walks into its cell or bumps into it, so changing an obstacle rewinds the
+/// walk to that step and walks on from there. Obstacles the guard never
+/// reaches cost nothing.
+pub struct IncrementalSim {
+  grid:        Grid,
+  rules:       MovementRules,
+  states:      Vec<GuardState>,
+  outcome:     Outcome,
+  /// The step that the guard comes back to, if the walk loops.
+  loop_start:  Option<usize>,
+  /// For each cell and direction, the step where the guard was in that
+  /// state.
+  steps:       Vec<[Option<usize>; 8]>,
+  /// For each cell, how many steps the guard spent in it.
+  visits:      Vec<usize>,
+  /// For each cell, the first step where the guard walked into it or bumped
+  /// into it.
+  first_touch: Vec<Option<usize>>,
+}
+
+impl IncrementalSim {
+  pub fn new(grid: Grid, start: GuardState, rules: MovementRules) -> Self {
+    let cell_count = grid.width() * grid.height();
+    let mut sim = Self {
+      grid,
+      rules,
+      states: Vec::new(),
+      outcome: Outcome::Exit,
+      loop_start: None,
+      steps: vec![[None; 8]; cell_count],
+      visits: vec![0; cell_count],
+      first_touch: vec![None; cell_count],
+    };
+    sim.push(start, &mut BTreeSet::new());
+    sim.walk(&mut BTreeSet::new());
+    sim
+  }
+
+  pub fn grid(&self) -> &Grid { &self.grid }
+
+  /// Every state of the walk, in order.
+  pub fn path(&self) -> &[GuardState] { &self.states }
+
+  pub fn outcome(&self) -> Outcome { self.outcome }
+
+  /// How many steps one lap of the loop takes, if the walk loops.
+  pub fn loop_length(&self) -> Option<usize> {
+    self.loop_start.map(|start| self.states.len() - start)
+  }
+
+  /// How many distinct cells the guard visits.
+  pub fn cells(&self) -> usize {
+    self.visits.iter().filter(|&&v| v > 0).count()
+  }
+
+  pub fn add_obstacle(
+    &mut self,
+    pos: (usize, usize),
+  ) -> Result<Change, ObstacleError> {
+    self.check(pos)?;
+    if self.grid.get(pos.0, pos.1) {
+      return Err(ObstacleError::AlreadyBlocked(pos));
+    }
+    Ok(self.toggle(pos))
+  }
+
+  pub fn remove_obstacle(
+    &mut self,
+    pos: (usize, usize),
+  ) -> Result<Change, ObstacleError> {
+    self.check(pos)?;
+    if !self.grid.get(pos.0, pos.1) {
+      return Err(ObstacleError::NotBlocked(pos));
+    }
+    Ok(self.toggle(pos))
+  }
+
+  fn check(&self, pos: (usize, usize)) -> Result<(), ObstacleError> {
+    check_obstacle(&self.grid, self.states[0].pos, pos)
+  }
+
+  fn toggle(&mut self, pos: (usize, usize)) -> Change {
+    let cell = self.cell(pos);
+    self.grid.set(pos.0, pos.1, !self.grid.get(pos.0, pos.1));
+
+    let Some(touch) = self.first_touch[cell] else {
+      return Change {
+        kept: self.states.len(),
+        ..Change::default()
+      };
+    };
+
+    // rewind to the step that reaches the obstacle, which happens the same
+    // way with or without it
+    let mut removed = BTreeSet::new();
+    while self.states.len() > touch + 1 {
+      let state = self.states.pop().unwrap();
+      let index = self.states.len();
+      let cell = self.cell(state.pos);
+      self.steps[cell][state.dir.as_usize()] = None;
+      self.visits[cell] -= 1;
+      if self.visits[cell] == 0 {
+        removed.insert(state.pos);
+      }
+      if let Some(touched) = target(&self.grid, self.rules, state) {
+        let touched = self.cell(touched);
+        if self.first_touch[touched] == Some(index) {
+          self.first_touch[touched] = None;
+        }
+      }
+    }
+
+    let mut added = BTreeSet::new();
+    self.walk(&mut added);
+
+    Change {
+      kept:    touch + 1,
+      added:   added.difference(&removed).copied().collect(),
+      removed: removed.difference(&added).copied().collect(),
+    }
+  }
+
+  /// Walks on from the last state until the walk ends.
+  fn walk(&mut self, added: &mut BTreeSet<(usize, usize)>) {
+    self.loop_start = None;
+    loop {
+      let state = *self.states.last().unwrap();
+      match step(&self.grid, self.rules, state) {
+        Ok(next) => {
+          let seen = self.steps[self.cell(next.pos)][next.dir.as_usize()];
+          if seen.is_some() {
+            self.outcome = Outcome::Loop;
+            self.loop_start = seen;
+            return;
+          }
+          self.push(next, added);
+        }
+        Err(outcome) => {
+          self.outcome = outcome;
+          return;
+        }
+      }
+    }
+  }
+
+  fn push(&mut self, state: GuardState, added: &mut BTreeSet<(usize, usize)>) {
+    let index = self.states.len();
+    let cell = self.cell(state.pos);
+    self.steps[cell][state.dir.as_usize()] = Some(index);
+    self.visits[cell] += 1;
+    if self.visits[cell] == 1 {
+      added.insert(state.pos);
+    }
+    if let Some(touched) = target(&self.grid, self.rules, state) {
+      let touched = self.cell(touched);
+      self.first_touch[touched].get_or_insert(index);
+    }
+    self.states.push(state);
+  }
+
+  fn cell(&self, pos: (usize, usize)) -> usize {
+    pos.1 * self.grid.width() + pos.0
+  }
+}
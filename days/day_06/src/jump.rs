@@ -0,0 +1,130 @@
+use direction::Direction4;
+
+use crate::{detect::LoopDetector, grid::Grid, sim::GuardState};
+
+/// For every cell and direction, where a guard walking from that cell stops:
+/// the last free cell before the next obstacle, or `None` if it walks off the
+/// grid. This turns each straight run of the walk into a single lookup.
+pub struct JumpTable {
+  width:  usize,
+  height: usize,
+  stops:  Vec<[Option<(usize, usize)>; 4]>,
+}
+
+impl JumpTable {
+  pub fn new(grid: &Grid) -> Self {
+    let (width, height) = (grid.width(), grid.height());
+    let mut stops = vec![[None; 4]; width * height];
+
+    for dir in Direction4::iter() {
+      let (dx, dy) = dir.vector();
+
+      // visit the cells so that the cell in front of each one comes first,
+      // and the stop of each cell follows from that cell's
+      let xs: Vec<_> = if dx > 0 {
+        (0..width).rev().collect()
+      } else {
+        (0..width).collect()
+      };
+      let ys: Vec<_> = if dy > 0 {
+        (0..height).rev().collect()
+      } else {
+        (0..height).collect()
+      };
+
+      for &y in &ys {
+        for &x in &xs {
+          let (next_x, next_y) = (x as isize + dx, y as isize + dy);
+          let stop = if next_x < 0
+            || next_x >= width as isize
+            || next_y < 0
+            || next_y >= height as isize
+          {
+            None
+          } else if grid.get(next_x as usize, next_y as usize) {
+            Some((x, y))
+          } else {
+            stops[next_y as usize * width + next_x as usize][dir.as_usize()]
+          };
+          stops[y * width + x][dir.as_usize()] = stop;
+        }
+      }
+    }
+
+    Self {
+      width,
+      height,
+      stops,
+    }
+  }
+
+  /// Where a guard walking from `pos` in `dir` stops, with an extra obstacle
+  /// at `obstacle` if there is one.
+  pub fn stop(
+    &self,
+    pos: (usize, usize),
+    dir: Direction4,
+    obstacle: Option<(usize, usize)>,
+  ) -> Option<(usize, usize)> {
+    let stop = self.stops[pos.1 * self.width + pos.0][dir.as_usize()];
+
+    // how many steps from `pos` along `dir` a cell is, if it is on that ray
+    let steps = |cell: (usize, usize)| -> Option<usize> {
+      let (dx, dy) = dir.vector();
+      match (dx, dy) {
+        (0, -1) if cell.0 == pos.0 && cell.1 < pos.1 => Some(pos.1 - cell.1),
+        (0, 1) if cell.0 == pos.0 && cell.1 > pos.1 => Some(cell.1 - pos.1),
+        (-1, 0) if cell.1 == pos.1 && cell.0 < pos.0 => Some(pos.0 - cell.0),
+        (1, 0) if cell.1 == pos.1 && cell.0 > pos.0 => Some(cell.0 - pos.0),
+        _ => None,
+      }
+    };
+
+    let Some(obstacle) = obstacle else {
+      return stop;
+    };
+    let Some(obstacle_steps) = steps(obstacle) else {
+      return stop;
+    };
+    let stop_steps = stop.map(|stop| steps(stop).unwrap_or(0));
+    if stop_steps.is_some_and(|stop_steps| stop_steps < obstacle_steps) {
+      return stop;
+    }
+
+    // the extra obstacle is in the way, so stop just in front of it
+    let (dx, dy) = dir.vector();
+    Some((
+      (obstacle.0 as isize - dx) as usize,
+      (obstacle.1 as isize - dy) as usize,
+    ))
+  }
+
+  /// The state after the guard's next turn, or `None` if it walks off the
+  /// grid first.
+  pub fn next_turn(
+    &self,
+    state: GuardState<Direction4>,
+    obstacle: (usize, usize),
+  ) -> Option<GuardState<Direction4>> {
+    let pos = self.stop(state.pos, state.dir, Some(obstacle))?;
+    Some(GuardState {
+      pos,
+      dir: state.dir.turn_right(),
+    })
+  }
+
+  /// Whether a guard starting at `start` loops with an extra obstacle at
+  /// `obstacle`.
+  pub fn loops(
+    &self,
+    start: GuardState<Direction4>,
+    obstacle: (usize, usize),
+  ) -> bool {
+    self
+      .check_loop(start, obstacle, LoopDetector::default())
+      .looped
+  }
+
+  pub fn width(&self) -> usize { self.width }
+  pub fn height(&self) -> usize { self.height }
+}
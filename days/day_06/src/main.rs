@@ -1,53 +1,47 @@
+mod animate;
+mod detect;
+mod generate;
 mod grid;
+mod guards;
+mod incremental;
+mod jump;
+mod rules;
+mod sim;
 
-use std::path::{Path, PathBuf};
+use std::{
+  collections::HashSet,
+  io::{BufRead, Write},
+  path::{Path, PathBuf},
+};
 
+use direction::{Direction4, Direction8};
+use rand::{Rng, SeedableRng, rngs::StdRng};
 use rayon::prelude::*;
 
-use self::grid::Grid;
+use self::{
+  detect::LoopDetector,
+  grid::Grid,
+  incremental::IncrementalSim,
+  jump::JumpTable,
+  rules::{AtObstacle, Directions, MovementRules, Topology},
+  sim::{GuardSim, GuardState, Outcome},
+};
 
 #[derive(Clone)]
 struct Input {
-  grid:         Grid,
-  starting_pos: (usize, usize),
-  starting_dir: Direction,
+  grid:   Grid,
+  /// Every guard on the map, in reading order.
+  guards: Vec<GuardState>,
 }
 
-#[derive(Clone, Copy, Debug)]
-enum Direction {
-  Up,
-  Right,
-  Down,
-  Left,
-}
-
-impl Direction {
-  fn turn_right(&self) -> Self {
-    match self {
-      Direction::Up => Direction::Right,
-      Direction::Right => Direction::Down,
-      Direction::Down => Direction::Left,
-      Direction::Left => Direction::Up,
-    }
-  }
-
-  fn vector(&self) -> (isize, isize) {
-    match self {
-      Direction::Up => (0, -1),
-      Direction::Right => (1, 0),
-      Direction::Down => (0, 1),
-      Direction::Left => (-1, 0),
-    }
-  }
-
-  fn as_usize(&self) -> usize {
-    match self {
-      Direction::Up => 0,
-      Direction::Right => 1,
-      Direction::Down => 2,
-      Direction::Left => 3,
-    }
-  }
+/// An obstacle position that makes the guard loop.
+#[derive(Debug, PartialEq, Eq)]
+struct LoopObstacle {
+  obstacle: (usize, usize),
+  /// The first state of the loop that the guard reaches.
+  entry:    GuardState,
+  /// How many steps, counting turns, one lap of the loop takes.
+  length:   usize,
 }
 
 impl Input {
@@ -62,8 +56,7 @@ impl Input {
     let mut grid = Vec::new();
     let mut width = 0;
     let mut height = 0;
-    let mut starting_pos = (0, 0);
-    let mut starting_dir = Direction::Up;
+    let mut guards = Vec::new();
 
     for (y, line) in content
       .lines()
@@ -78,73 +71,202 @@ impl Input {
         match c {
           '.' => grid.push(false),
           '#' => grid.push(true),
-          '^' => {
-            grid.push(false);
-            starting_pos = (x, y);
-            starting_dir = Direction::Up;
-          }
-          '>' => {
-            grid.push(false);
-            starting_pos = (x, y);
-            starting_dir = Direction::Right;
-          }
-          '<' => {
+          c => {
+            let dir = Direction4::from_glyph(c).unwrap_or_else(|| {
+              panic!("unexpected character in input file: {c:?}")
+            });
             grid.push(false);
-            starting_pos = (x, y);
-            starting_dir = Direction::Left;
+            guards.push(GuardState {
+              pos: (x, y),
+              dir: dir.into(),
+            });
           }
-          'v' => {
-            grid.push(false);
-            starting_pos = (x, y);
-            starting_dir = Direction::Down;
-          }
-          c => panic!("unexpected character in input file: {c:?}"),
         }
       }
     }
 
     Self {
       grid: Grid::from_vec(width, height, grid),
-      starting_pos,
-      starting_dir,
+      guards,
     }
   }
 
-  fn part_1(&self) -> usize {
-    // walk through the grid. if we hit a wall, turn right. if we go out of
-    // bounds, stop.
+  /// The one guard that the puzzle parts are about. Maps with any other
+  /// number of guards are rejected, rather than picking one of them.
+  fn start(&self) -> GuardState {
+    match self.guards.as_slice() {
+      [guard] => *guard,
+      guards => panic!("expected exactly one guard, found {}", guards.len()),
+    }
+  }
 
-    let mut visited_grid = Grid::new(self.grid.width(), self.grid.height());
+  fn part_1(&self) -> usize { self.part_1_with(MovementRules::default()) }
 
-    let mut pos = self.starting_pos;
-    let mut dir = self.starting_dir;
+  /// Counts the cells the guard visits when moving by `rules`.
+  fn part_1_with(&self, rules: MovementRules) -> usize {
+    // walk through the grid. if we hit a wall, turn (or stop). if we go out
+    // of bounds, stop.
 
-    loop {
+    let mut visited_grid = Grid::new(self.grid.width(), self.grid.height());
+    let mut sim = GuardSim::with_rules(&self.grid, self.start(), rules);
+    for GuardState { pos, .. } in sim.by_ref() {
       visited_grid.set(pos.0, pos.1, true);
+    }
+    // with nowhere to leave, the walk can only end by looping or stopping
+    if rules.topology == Topology::Bounded {
+      assert_ne!(sim.outcome(), Some(Outcome::Loop), "guard never leaves");
+    }
 
-      let (dx, dy) = dir.vector();
-      let (x, y) = (pos.0 as isize + dx, pos.1 as isize + dy);
+    visited_grid.iter().filter(|&v| v).count()
+  }
 
-      if x < 0
-        || x >= self.grid.width() as isize
-        || y < 0
-        || y >= self.grid.height() as isize
-      {
-        break;
-      }
+  /// The obstacle positions worth trying for part 2, each with the state of
+  /// the guard just before it first walks into that cell. An obstacle
+  /// anywhere else is never reached, so it can't change the walk.
+  ///
+  /// The states are for resuming the walk with the jump table, so they only
+  /// face straight, as they always do under the default rules.
+  fn candidates(&self) -> Vec<((usize, usize), GuardState<Direction4>)> {
+    let mut first_entries = Vec::new();
+    let mut entered = Grid::new(self.grid.width(), self.grid.height());
+    let start = self.start();
+    entered.set(start.pos.0, start.pos.1, true);
 
-      if self.grid.get(x as usize, y as usize) {
-        dir = dir.turn_right();
-        continue;
+    let sim = GuardSim::new(&self.grid, self.start());
+    let mut previous = self.start();
+    for state in sim {
+      if !entered.get(state.pos.0, state.pos.1) {
+        entered.set(state.pos.0, state.pos.1, true);
+        let previous = GuardState::<Direction4>::try_from(previous)
+          .expect("jump table only handles straight directions");
+        first_entries.push((state.pos, previous));
       }
+      previous = state;
+    }
+
+    first_entries
+  }
+
+  fn part_2(&self) -> usize { self.part_2_with(LoopDetector::default()) }
+
+  fn part_2_with(&self, detector: LoopDetector) -> usize {
+    let table = JumpTable::new(&self.grid);
+
+    // the walk up to the obstacle doesn't change, so resume it from just
+    // before the guard would first walk into the obstacle
+    self
+      .candidates()
+      .into_par_iter()
+      .filter(|&(obstacle, resume)| {
+        table.check_loop(resume, obstacle, detector).looped
+      })
+      .count()
+  }
+
+  /// Finds every obstacle position that makes the guard loop, along with the
+  /// loop it ends up in, ordered by position.
+  fn loop_obstacles(&self) -> Vec<LoopObstacle> {
+    let table = JumpTable::new(&self.grid);
 
-      pos = (x as usize, y as usize);
+    let mut obstacles: Vec<_> = self
+      .candidates()
+      .into_par_iter()
+      .filter(|&(obstacle, resume)| table.loops(resume, obstacle))
+      .map(|(obstacle, _)| {
+        // step through the walk again to find where the loop starts
+        let mut grid = self.grid.clone();
+        grid.set(obstacle.0, obstacle.1, true);
+        let mut sim = GuardSim::new(&grid, self.start());
+        let states: Vec<_> = sim.by_ref().collect();
+        let entry = sim.loop_start().expect("walk did not loop");
+        let entry_index = states.iter().position(|&s| s == entry).unwrap();
+
+        LoopObstacle {
+          obstacle,
+          entry,
+          length: states.len() - entry_index,
+        }
+      })
+      .collect();
+
+    obstacles.sort_by_key(|o| (o.obstacle.1, o.obstacle.0));
+    obstacles
+  }
+
+  /// Draws the grid with each obstacle from [`Input::loop_obstacles`] as an
+  /// `O`.
+  fn render_loop_obstacles(&self, obstacles: &[LoopObstacle]) -> String {
+    let start = self.start();
+    let mut out = String::new();
+    for y in 0..self.grid.height() {
+      for x in 0..self.grid.width() {
+        let c = if obstacles.iter().any(|o| o.obstacle == (x, y)) {
+          'O'
+        } else if (x, y) == start.pos {
+          start.dir.glyph()
+        } else if self.grid.get(x, y) {
+          '#'
+        } else {
+          '.'
+        };
+        out.push(c);
+      }
+      out.push('\n');
     }
+    out
+  }
 
-    visited_grid.iter().filter(|&v| v).count()
+  /// Solves part 2 by trying an obstacle in every free cell, and walking
+  /// from the start each time.
+  fn part_2_brute_force(&self) -> usize {
+    let table = JumpTable::new(&self.grid);
+    let cell_count = self.grid.width() * self.grid.height();
+    let start = GuardState::<Direction4>::try_from(self.start())
+      .expect("jump table only handles straight directions");
+
+    (0..cell_count)
+      .into_par_iter()
+      .map(|i| (i % self.grid.width(), i / self.grid.width()))
+      // the new obstacle can't go on an existing one or on the guard
+      .filter(|&(x, y)| !self.grid.get(x, y) && (x, y) != start.pos)
+      .filter(|&obstacle| table.loops(start, obstacle))
+      .count()
+  }
+
+  /// Solves part 2 by adding and removing each candidate obstacle on one
+  /// [`IncrementalSim`], which only walks again from where the guard first
+  /// reaches the obstacle.
+  fn part_2_incremental(&self) -> usize {
+    let mut sim = IncrementalSim::new(
+      self.grid.clone(),
+      self.start(),
+      MovementRules::default(),
+    );
+
+    self
+      .candidates()
+      .into_iter()
+      .filter(|&(obstacle, _)| {
+        sim.add_obstacle(obstacle).expect("failed to add obstacle");
+        let looped = sim.outcome() == Outcome::Loop;
+        sim
+          .remove_obstacle(obstacle)
+          .expect("failed to remove obstacle");
+        looped
+      })
+      .count()
   }
 
-  fn part_2(&self) -> usize {
+  /// Solves part 2 by stepping the guard one cell at a time through a copy of
+  /// the grid for every candidate obstacle.
+  fn part_2_stepwise(&self) -> usize {
+    self.part_2_stepwise_with(MovementRules::default())
+  }
+
+  /// Like [`Input::part_2_stepwise`], with the guard moving by `rules`. The
+  /// jump table only knows the default rules, so this is the only way to
+  /// solve part 2 under any others.
+  fn part_2_stepwise_with(&self, rules: MovementRules) -> usize {
     let cell_count = self.grid.width() * self.grid.height();
 
     let mutate_input = |i: usize| -> Option<Self> {
@@ -154,7 +276,7 @@ impl Input {
       let y = i / self.grid.width();
 
       // if the cell is already filled or is the starting position, return None
-      if input.grid.get(x, y) || (x, y) == input.starting_pos {
+      if input.grid.get(x, y) || (x, y) == input.start().pos {
         return None;
       }
 
@@ -164,52 +286,413 @@ impl Input {
       Some(input)
     };
     let check_sim_for_loops = |input: &Input| -> bool {
-      let mut visited_grid_directions =
-        vec![[false; 4]; input.grid.width() * input.grid.height()];
-
-      let mut pos = input.starting_pos;
-      let mut dir = input.starting_dir;
-
-      loop {
-        if visited_grid_directions[pos.1 * input.grid.width() + pos.0]
-          [dir.as_usize()]
-        {
-          return true;
-        }
-        visited_grid_directions[pos.1 * input.grid.width() + pos.0]
-          [dir.as_usize()] = true;
-
-        let (dx, dy) = dir.vector();
-        let (x, y) = (pos.0 as isize + dx, pos.1 as isize + dy);
-
-        if x < 0
-          || x >= input.grid.width() as isize
-          || y < 0
-          || y >= input.grid.height() as isize
-        {
-          break;
-        }
+      GuardSim::with_rules(&input.grid, input.start(), rules).run()
+        == Outcome::Loop
+    };
 
-        if input.grid.get(x as usize, y as usize) {
-          dir = dir.turn_right();
-          continue;
-        }
+    (0..cell_count)
+      .into_par_iter()
+      .filter_map(mutate_input)
+      .filter(check_sim_for_loops)
+      .count()
+  }
 
-        pos = (x as usize, y as usize);
-      }
+  /// Part 2 for a torus or a walled grid, where the guard can't leave, so it
+  /// loops with or without an obstacle unless it stops. Counting the
+  /// obstacles that make it loop would count every free cell, so this counts
+  /// the ones that change the loop it ends up in, or end it. Two loops are
+  /// the same if they hold the same states.
+  fn part_2_loop_changes_with(&self, rules: MovementRules) -> usize {
+    let cell_count = self.grid.width() * self.grid.height();
+    let start = self.start();
 
-      false
+    // the states of the loop the guard ends up in, if it loops
+    let final_loop = |grid: &Grid| -> Option<HashSet<GuardState>> {
+      let mut sim = GuardSim::with_rules(grid, start, rules);
+      let states: Vec<_> = sim.by_ref().collect();
+      let entry = sim.loop_start()?;
+      let entry_index = states.iter().position(|&s| s == entry).unwrap();
+      Some(states[entry_index..].iter().copied().collect())
     };
+    let unchanged = final_loop(&self.grid);
 
     (0..cell_count)
       .into_par_iter()
-      .filter_map(mutate_input)
-      .filter(check_sim_for_loops)
+      .map(|i| (i % self.grid.width(), i / self.grid.width()))
+      // the new obstacle can't go on an existing one or on the guard
+      .filter(|&(x, y)| !self.grid.get(x, y) && (x, y) != start.pos)
+      .filter(|&(x, y)| {
+        let mut grid = self.grid.clone();
+        grid.set(x, y, true);
+        final_loop(&grid) != unchanged
+      })
       .count()
   }
 }
 
+/// Replays the guard's walk.
+///
+/// - `--animate[=ms]` redraws the grid in place, waiting the given number of
+///   milliseconds between frames (50 by default).
+/// - `--frames=DIR` writes each frame to a numbered file in `DIR` instead.
+/// - `--obstacle=x,y` adds an obstacle, as in part 2.
+fn replay(input: &Input, rules: MovementRules, args: &[String]) {
+  let delay = args
+    .iter()
+    .find_map(|arg| arg.strip_prefix("--animate="))
+    .map_or(50, |ms| ms.parse().expect("failed to parse frame delay"));
+  let obstacle = args
+    .iter()
+    .find_map(|arg| arg.strip_prefix("--obstacle="))
+    .map(|pos| {
+      let (x, y) = pos.split_once(',').expect("expected an obstacle like 3,6");
+      (
+        x.parse().expect("failed to parse obstacle x"),
+        y.parse().expect("failed to parse obstacle y"),
+      )
+    });
+  let frames_dir = args
+    .iter()
+    .find_map(|arg| arg.strip_prefix("--frames="))
+    .map(PathBuf::from);
+
+  if let Some(dir) = &frames_dir {
+    std::fs::create_dir_all(dir).expect("failed to create frames directory");
+  }
+  let mut frame_number = 0;
+  animate::animate(&input.grid, input.start(), rules, obstacle, |frame| {
+    match &frames_dir {
+      Some(dir) => {
+        let path = dir.join(format!("frame_{frame_number:05}.txt"));
+        std::fs::write(path, frame).expect("failed to write frame");
+      }
+      None => {
+        // move the cursor home and clear the screen before each frame
+        print!("\x1b[H\x1b[2J{frame}");
+        std::io::stdout().flush().expect("failed to flush stdout");
+        std::thread::sleep(std::time::Duration::from_millis(delay));
+      }
+    }
+    frame_number += 1;
+  })
+  .unwrap_or_else(|e| panic!("{e}"));
+}
+
+/// Reads commands from stdin to add and remove obstacles, and reports how
+/// the guard's walk changes after each one.
+///
+/// - `add x,y` and `remove x,y` change an obstacle.
+/// - `show` draws the grid with the guard's path.
+fn explore(input: &Input, rules: MovementRules) {
+  let mut sim = IncrementalSim::new(input.grid.clone(), input.start(), rules);
+  let summary = |sim: &IncrementalSim| match sim.loop_length() {
+    Some(lap) => format!(
+      "guard loops after {} steps, {lap} steps per lap, over {} cells",
+      sim.path().len() - lap,
+      sim.cells()
+    ),
+    None => format!(
+      "guard {} after {} steps, over {} cells",
+      if sim.outcome() == Outcome::Stop {
+        "stops"
+      } else {
+        "leaves"
+      },
+      sim.path().len(),
+      sim.cells()
+    ),
+  };
+  println!("{}", summary(&sim));
+
+  for line in std::io::stdin().lock().lines() {
+    let line = line.expect("failed to read from stdin");
+    let (command, pos) =
+      line.trim().split_once(' ').unwrap_or((line.trim(), ""));
+    let pos = pos.split_once(',').and_then(|(x, y)| {
+      Some((x.trim().parse().ok()?, y.trim().parse().ok()?))
+    });
+
+    let change = match (command, pos) {
+      ("add", Some(pos)) => sim.add_obstacle(pos),
+      ("remove", Some(pos)) => sim.remove_obstacle(pos),
+      ("show", _) => {
+        let mut trail = Grid::new(sim.grid().width(), sim.grid().height());
+        for state in sim.path() {
+          trail.set(state.pos.0, state.pos.1, true);
+        }
+        for y in 0..trail.height() {
+          let row: String = (0..trail.width())
+            .map(|x| match (sim.grid().get(x, y), trail.get(x, y)) {
+              (true, _) => '#',
+              (false, true) => 'X',
+              (false, false) => '.',
+            })
+            .collect();
+          println!("{row}");
+        }
+        continue;
+      }
+      ("", _) => continue,
+      _ => {
+        println!("expected `add x,y`, `remove x,y` or `show`");
+        continue;
+      }
+    };
+
+    match change {
+      Ok(change) => println!(
+        "{}; kept {} steps, {} cells added, {} removed",
+        summary(&sim),
+        change.kept,
+        change.added.len(),
+        change.removed.len()
+      ),
+      Err(e) => println!("{e}"),
+    }
+  }
+}
+
+/// Prints a generated map to stdout, and its expected answers to stderr.
+///
+/// Usage: `generate <width> <height> <density> <seed> [--exits | --loops=N]`
+fn generate(args: &[String]) {
+  let parse_arg = |i: usize, name: &str| -> f64 {
+    args
+      .get(i)
+      .unwrap_or_else(|| panic!("missing {name} argument"))
+      .parse()
+      .unwrap_or_else(|_| panic!("failed to parse {name} argument"))
+  };
+
+  let guarantee = if let Some(loops) =
+    args.iter().find_map(|arg| arg.strip_prefix("--loops="))
+  {
+    generate::Guarantee::LoopPositions(
+      loops.parse().expect("failed to parse loop position count"),
+    )
+  } else if args.iter().any(|arg| arg == "--exits") {
+    generate::Guarantee::Exits
+  } else {
+    generate::Guarantee::Nothing
+  };
+
+  let map = generate::Config {
+    width: parse_arg(0, "width") as usize,
+    height: parse_arg(1, "height") as usize,
+    density: parse_arg(2, "density"),
+    seed: parse_arg(3, "seed") as u64,
+    guarantee,
+  }
+  .generate()
+  .unwrap_or_else(|e| panic!("failed to generate map: {e}"));
+
+  print!("{}", map.text);
+  match (map.part_1, map.part_2) {
+    (Some(part_1), Some(part_2)) => {
+      eprintln!("Part 1: {part_1}");
+      eprintln!("Part 2: {part_2}");
+    }
+    _ => eprintln!("The guard loops"),
+  }
+}
+
+/// Walks every guard on the map together, and reports how each walk ended,
+/// where guards collided and how much of the map they covered.
+fn report_guards(input: &Input, rules: MovementRules) {
+  let now = std::time::Instant::now();
+  let report = guards::walk_guards(&input.grid, &input.guards, rules);
+  let elapsed = now.elapsed().as_secs_f32() * 1000.0;
+
+  for (i, walk) in report.walks.iter().enumerate() {
+    println!(
+      "Guard {i} at {:?} facing {:?}: {:?} after visiting {} cells",
+      walk.start.pos, walk.start.dir, walk.outcome, walk.visited
+    );
+  }
+  for collision in &report.collisions {
+    println!(
+      "Guards {} and {} {} at {:?} on step {}",
+      collision.guards.0,
+      collision.guards.1,
+      if collision.swap { "pass" } else { "meet" },
+      collision.pos,
+      collision.step
+    );
+  }
+  println!(
+    "Covered {} cells with {} collisions, in {elapsed:.3}ms",
+    report.coverage,
+    report.collisions.len()
+  );
+}
+
+/// Times part 2 with and without the jump table, and with and without
+/// restricting the obstacles to the guard's path.
+fn bench(input: &Input) {
+  let now = std::time::Instant::now();
+  let stepwise = input.part_2_stepwise();
+  println!(
+    "Stepwise: {stepwise}, in {:.3}ms",
+    now.elapsed().as_secs_f32() * 1000.0
+  );
+
+  let now = std::time::Instant::now();
+  let jumps = input.part_2_brute_force();
+  println!(
+    "Jump table: {jumps}, in {:.3}ms",
+    now.elapsed().as_secs_f32() * 1000.0
+  );
+
+  let now = std::time::Instant::now();
+  let path = input.part_2();
+  println!(
+    "Jump table, path only: {path}, in {:.3}ms",
+    now.elapsed().as_secs_f32() * 1000.0
+  );
+
+  let now = std::time::Instant::now();
+  let incremental = input.part_2_incremental();
+  println!(
+    "Incremental: {incremental}, in {:.3}ms",
+    now.elapsed().as_secs_f32() * 1000.0
+  );
+
+  assert_eq!(stepwise, jumps, "jump table disagrees with stepping");
+  assert_eq!(path, incremental, "incremental walk disagrees with jumps");
+  assert_eq!(jumps, path, "path candidates disagree with brute force");
+}
+
+/// Times part 2 with each loop detector, and reports the most memory any
+/// one loop check used.
+fn bench_detectors(input: &Input) {
+  let table = JumpTable::new(&input.grid);
+  let candidates = input.candidates();
+
+  for detector in [
+    LoopDetector::Visited,
+    LoopDetector::TurnsOnly,
+    LoopDetector::Brent,
+  ] {
+    let now = std::time::Instant::now();
+    let checks: Vec<_> = candidates
+      .par_iter()
+      .map(|&(obstacle, resume)| table.check_loop(resume, obstacle, detector))
+      .collect();
+    let elapsed = now.elapsed().as_secs_f32() * 1000.0;
+
+    println!(
+      "{detector:?}: {} loops, in {elapsed:.3}ms, at most {} bytes per check",
+      checks.iter().filter(|check| check.looped).count(),
+      checks.iter().map(|check| check.memory).max().unwrap_or(0)
+    );
+  }
+}
+
+/// Builds a grid of the given size with obstacles placed at random, and the
+/// guard facing up from the middle.
+fn synthetic_input(size: usize, density: f64, seed: u64) -> Input {
+  let mut rng = StdRng::seed_from_u64(seed);
+  let starting_pos = (size / 2, size / 2);
+
+  let mut grid = Grid::new(size, size);
+  for y in 0..size {
+    for x in 0..size {
+      if (x, y) != starting_pos && rng.gen_bool(density) {
+        grid.set(x, y, true);
+      }
+    }
+  }
+
+  Input {
+    grid,
+    guards: vec![GuardState {
+      pos: starting_pos,
+      dir: Direction8::Up,
+    }],
+  }
+}
+
 fn main() {
+  let args: Vec<_> = std::env::args().skip(1).collect();
+
+  // `--turn=right|left|around`, `--eight-way`, `--stop-at-obstacle` and
+  // `--topology=bounded|torus|walled` change how the guard moves
+  let rules = MovementRules {
+    turn:        args
+      .iter()
+      .find_map(|arg| arg.strip_prefix("--turn="))
+      .map(|turn| turn.parse().unwrap_or_else(|e| panic!("{e}")))
+      .unwrap_or_default(),
+    directions:  if args.iter().any(|arg| arg == "--eight-way") {
+      Directions::Eight
+    } else {
+      Directions::Four
+    },
+    at_obstacle: if args.iter().any(|arg| arg == "--stop-at-obstacle") {
+      AtObstacle::Stop
+    } else {
+      AtObstacle::Turn
+    },
+    topology:    args
+      .iter()
+      .find_map(|arg| arg.strip_prefix("--topology="))
+      .map(|topology| topology.parse().unwrap_or_else(|e| panic!("{e}")))
+      .unwrap_or_default(),
+  };
+
+  // the jump table, which the loop detectors, the benches, the generator and
+  // `--show-loops` are built on, only knows the default rules, so reject the
+  // others rather than quietly ignoring them
+  if rules != MovementRules::default() {
+    let jump_table_only = args
+      .first()
+      .filter(|arg| {
+        matches!(arg.as_str(), "bench" | "bench-detectors" | "generate")
+      })
+      .or_else(|| {
+        args
+          .iter()
+          .find(|arg| *arg == "--show-loops" || arg.starts_with("--detector="))
+      });
+    if let Some(arg) = jump_table_only {
+      panic!("{arg} only works with the default movement rules");
+    }
+  }
+
+  // `bench-detectors [size] [density] [seed]` compares the loop detectors on
+  // a synthetic grid, or on the puzzle input if no size is given
+  if args.first().is_some_and(|arg| arg == "bench-detectors") {
+    let arg = |i: usize| {
+      args.get(i).map(|arg| {
+        arg
+          .parse::<f64>()
+          .unwrap_or_else(|_| panic!("failed to parse argument {arg:?}"))
+      })
+    };
+    let input = match arg(1) {
+      Some(size) => synthetic_input(
+        size as usize,
+        arg(2).unwrap_or(0.05),
+        arg(3).unwrap_or(0.0) as u64,
+      ),
+      None => Input::from_file(PathBuf::from("inputs/day_06.txt").as_path()),
+    };
+    bench_detectors(&input);
+    return;
+  }
+
+  if args.first().is_some_and(|arg| arg == "generate") {
+    generate(&args[1..]);
+    return;
+  }
+
+  // `--detector=visited|turns|brent` picks how part 2 detects loops
+  let detector = args
+    .iter()
+    .find_map(|arg| arg.strip_prefix("--detector="))
+    .map(|name| name.parse().unwrap_or_else(|e| panic!("{e}")))
+    .unwrap_or_default();
+
   let now = std::time::Instant::now();
   let input = Input::from_file(PathBuf::from("inputs/day_06.txt").as_path());
   println!(
@@ -217,14 +700,66 @@ fn main() {
     now.elapsed().as_secs_f32() * 1000.0
   );
 
+  if args.first().is_some_and(|arg| arg == "bench") {
+    bench(&input);
+    return;
+  }
+
+  if args.first().is_some_and(|arg| arg == "explore") {
+    explore(&input, rules);
+    return;
+  }
+
+  // the puzzle parts are about a single guard, so with several, walk them
+  // all together instead
+  if input.guards.len() > 1 {
+    report_guards(&input, rules);
+    return;
+  }
+
+  // `--show-loops` lists the obstacle positions that make the guard loop, and
+  // draws them on the grid
+  if args.iter().any(|arg| arg == "--show-loops") {
+    let obstacles = input.loop_obstacles();
+    print!("{}", input.render_loop_obstacles(&obstacles));
+    for LoopObstacle {
+      obstacle,
+      entry,
+      length,
+    } in &obstacles
+    {
+      println!(
+        "{obstacle:?}: loop of {length} steps, entered at {:?} facing {:?}",
+        entry.pos, entry.dir
+      );
+    }
+  }
+
+  if args.iter().any(|arg| arg.starts_with("--animate"))
+    || args.iter().any(|arg| arg.starts_with("--frames="))
+  {
+    replay(&input, rules, &args);
+    return;
+  }
+
   let now = std::time::Instant::now();
-  let part_1 = input.part_1();
+  let part_1 = if rules == MovementRules::default() {
+    input.part_1()
+  } else {
+    input.part_1_with(rules)
+  };
   println!(
     "Part 1: {part_1}, in {:.3}ms",
     now.elapsed().as_secs_f32() * 1000.0
   );
   let now = std::time::Instant::now();
-  let part_2 = input.part_2();
+  let part_2 = if rules == MovementRules::default() {
+    input.part_2_with(detector)
+  } else if rules.topology == Topology::Bounded {
+    input.part_2_stepwise_with(rules)
+  } else {
+    input.part_2_loop_changes_with(rules)
+  };
   println!(
     "Part 2: {part_2}, in {:.3}ms",
     now.elapsed().as_secs_f32() * 1000.0
@@ -259,4 +794,512 @@ mod tests {
     let input = Input::from_string(MINI_INPUT.to_string());
     assert_eq!(input.part_2(), 6);
   }
+
+  #[test]
+  fn part_2_stepwise_example() {
+    let input = Input::from_string(MINI_INPUT.to_string());
+    assert_eq!(input.part_2_stepwise(), 6);
+    assert_eq!(input.part_2_brute_force(), 6);
+  }
+
+  #[test]
+  fn part_2_path_candidates_match_brute_force() {
+    let mut input = Input::from_string(MINI_INPUT.to_string());
+    assert_eq!(input.candidates().len(), input.part_1() - 1);
+
+    // add each possible extra obstacle in turn, to get walks with a variety
+    // of shapes
+    for i in 0..input.grid.width() * input.grid.height() {
+      let (x, y) = (i % input.grid.width(), i / input.grid.width());
+      if input.grid.get(x, y) || (x, y) == input.start().pos {
+        continue;
+      }
+      input.grid.set(x, y, true);
+      if GuardSim::new(&input.grid, input.start()).run() == Outcome::Exit {
+        assert_eq!(input.part_2(), input.part_2_brute_force(), "{x}, {y}");
+      }
+      input.grid.set(x, y, false);
+    }
+  }
+
+  #[test]
+  fn loop_obstacles_example() {
+    const LOOP_OBSTACLES: &str = r#"
+....#.....
+.........#
+..........
+..#.......
+.......#..
+..........
+.#.O^.....
+......OO#.
+#O.O......
+......#O..
+"#;
+
+    let input = Input::from_string(MINI_INPUT.to_string());
+    let obstacles = input.loop_obstacles();
+    assert_eq!(
+      input.render_loop_obstacles(&obstacles),
+      LOOP_OBSTACLES.trim_start()
+    );
+
+    // with an obstacle next to its start, the guard loops around a 5x6
+    // rectangle: 18 moves and 4 turns
+    assert_eq!(obstacles[0], LoopObstacle {
+      obstacle: (3, 6),
+      entry:    GuardState {
+        pos: (4, 6),
+        dir: Direction8::Up,
+      },
+      length:   22,
+    });
+  }
+
+  #[test]
+  fn loop_detectors_agree() {
+    let mut loops = 0;
+    for seed in 0..20 {
+      let input = synthetic_input(40, 0.08, seed);
+      let table = JumpTable::new(&input.grid);
+
+      for (obstacle, resume) in input.candidates() {
+        let [visited, turns, brent] = [
+          LoopDetector::Visited,
+          LoopDetector::TurnsOnly,
+          LoopDetector::Brent,
+        ]
+        .map(|detector| table.check_loop(resume, obstacle, detector).looped);
+        assert_eq!(visited, turns, "seed {seed}, obstacle {obstacle:?}");
+        assert_eq!(visited, brent, "seed {seed}, obstacle {obstacle:?}");
+        loops += visited as usize;
+      }
+    }
+    assert!(loops > 0);
+  }
+
+  #[test]
+  fn animation_frames() {
+    const FIRST_FRAME: &str = r#"
+....#.....
+.........#
+..........
+..#.......
+.......#..
+..........
+.#..^.....
+........#.
+#.........
+......#...
+step 0
+"#;
+    const LOOP_FRAME: &str = r#"
+....#.....
+....+---+#
+....|...|.
+..#.|...|.
+....|..#|.
+....|...|.
+.#.O+---+.
+........#.
+#.........
+......#...
+guard loops after 0 steps, 22 steps per lap
+"#;
+
+    let input = Input::from_string(MINI_INPUT.to_string());
+
+    let mut frames = Vec::new();
+    animate::animate(
+      &input.grid,
+      input.start(),
+      MovementRules::default(),
+      None,
+      |frame| {
+        frames.push(frame.to_string());
+      },
+    )
+    .unwrap();
+    assert_eq!(frames[0], FIRST_FRAME.trim_start());
+    assert!(frames[1].contains("....^.....\n"));
+    assert!(frames[1].contains(".#..X.....\n"));
+    assert!(
+      frames
+        .last()
+        .unwrap()
+        .ends_with("guard leaves after 55 steps\n")
+    );
+
+    let mut frames = Vec::new();
+    animate::animate(
+      &input.grid,
+      input.start(),
+      MovementRules::default(),
+      Some((3, 6)),
+      |frame| {
+        frames.push(frame.to_string());
+      },
+    )
+    .unwrap();
+    assert_eq!(frames.last().unwrap(), LOOP_FRAME.trim_start());
+
+    // one past the right edge would otherwise block the start of the next
+    // row
+    let replay = |obstacle| {
+      animate::animate(
+        &input.grid,
+        input.start(),
+        MovementRules::default(),
+        Some(obstacle),
+        |_| (),
+      )
+    };
+    assert_eq!(
+      replay((10, 0)),
+      Err(incremental::ObstacleError::OutOfBounds((10, 0)))
+    );
+    assert_eq!(
+      replay((0, 10)),
+      Err(incremental::ObstacleError::OutOfBounds((0, 10)))
+    );
+    assert_eq!(
+      replay((4, 6)),
+      Err(incremental::ObstacleError::OnGuard((4, 6)))
+    );
+    assert_eq!(
+      replay((4, 0)),
+      Err(incremental::ObstacleError::AlreadyBlocked((4, 0)))
+    );
+  }
+
+  #[test]
+  fn movement_rules() {
+    use rules::Turn;
+
+    let input = Input::from_string(MINI_INPUT.to_string());
+    let rules = |turn, directions, at_obstacle| MovementRules {
+      turn,
+      directions,
+      at_obstacle,
+      topology: Topology::Bounded,
+    };
+
+    assert_eq!(
+      input.part_1_with(rules(Turn::Right, Directions::Four, AtObstacle::Turn)),
+      41
+    );
+    // up to the first obstacle, then straight out of the left edge
+    assert_eq!(
+      input.part_1_with(rules(Turn::Left, Directions::Four, AtObstacle::Turn)),
+      10
+    );
+    // up to the first obstacle, then straight back down and out
+    assert_eq!(
+      input.part_1_with(rules(
+        Turn::Around,
+        Directions::Four,
+        AtObstacle::Turn
+      )),
+      9
+    );
+    assert_eq!(
+      input.part_1_with(rules(Turn::Right, Directions::Four, AtObstacle::Stop)),
+      6
+    );
+    assert_eq!(
+      input.part_2_stepwise_with(MovementRules::default()),
+      input.part_2()
+    );
+
+    let eight = rules(Turn::Right, Directions::Eight, AtObstacle::Turn);
+    assert_eq!(eight.turn(Direction8::Up), Direction8::UpRight);
+    assert_eq!(eight.turn(Direction8::UpLeft), Direction8::Up);
+    let mut sim = GuardSim::with_rules(&input.grid, input.start(), eight);
+    let states: Vec<_> = sim.by_ref().collect();
+    assert!(states.iter().any(|s| s.dir == Direction8::UpRight));
+    assert_eq!(sim.outcome(), Some(Outcome::Exit));
+
+    let mut frames = Vec::new();
+    animate::animate(
+      &input.grid,
+      input.start(),
+      rules(Turn::Right, Directions::Four, AtObstacle::Stop),
+      None,
+      |frame| frames.push(frame.to_string()),
+    )
+    .unwrap();
+    assert!(
+      frames
+        .last()
+        .unwrap()
+        .ends_with("guard stops after 6 steps\n")
+    );
+  }
+
+  #[test]
+  fn incremental_obstacles() {
+    let input = Input::from_string(MINI_INPUT.to_string());
+    let mut sim = IncrementalSim::new(
+      input.grid.clone(),
+      input.start(),
+      MovementRules::default(),
+    );
+    let full_path = sim.path().to_vec();
+    assert_eq!(sim.cells(), 41);
+    assert_eq!(input.part_2_incremental(), 6);
+
+    let change = sim.add_obstacle((3, 6)).unwrap();
+    assert_eq!(sim.outcome(), Outcome::Loop);
+    assert_eq!(sim.loop_length(), Some(22));
+    assert!(change.kept > 0 && change.kept < full_path.len());
+    assert!(change.removed.contains(&(7, 9)));
+
+    // taking it away again gives back the same walk
+    let change = sim.remove_obstacle((3, 6)).unwrap();
+    assert_eq!(sim.path(), full_path);
+    assert_eq!(sim.outcome(), Outcome::Exit);
+    assert!(change.added.contains(&(7, 9)));
+
+    // an obstacle the guard never reaches changes nothing
+    let change = sim.add_obstacle((0, 9)).unwrap();
+    assert_eq!(change.kept, full_path.len());
+    assert!(change.added.is_empty() && change.removed.is_empty());
+
+    // removing an obstacle on the path lets the guard walk straight on
+    sim.remove_obstacle((4, 0)).unwrap();
+    assert_eq!(sim.outcome(), Outcome::Exit);
+    assert_eq!(sim.cells(), 7);
+
+    assert_eq!(
+      sim.add_obstacle((4, 6)),
+      Err(incremental::ObstacleError::OnGuard((4, 6)))
+    );
+    assert_eq!(
+      sim.remove_obstacle((1, 1)),
+      Err(incremental::ObstacleError::NotBlocked((1, 1)))
+    );
+  }
+
+  #[test]
+  fn generated_maps() {
+    use generate::{Config, Guarantee};
+
+    for seed in 0..4 {
+      for guarantee in [Guarantee::Exits, Guarantee::LoopPositions(5)] {
+        let map = Config {
+          width: 16,
+          height: 12,
+          density: 0.1,
+          seed,
+          guarantee,
+        }
+        .generate()
+        .unwrap();
+
+        // the answers hold for the map as written out and parsed back
+        let input = Input::from_string(map.text.clone());
+        assert_eq!(map.part_1, Some(input.part_1()));
+        assert_eq!(map.part_2, Some(input.part_2()));
+        assert_eq!(map.part_2, Some(input.part_2_stepwise()));
+        if guarantee == Guarantee::LoopPositions(5) {
+          assert_eq!(map.part_2, Some(5));
+        }
+      }
+    }
+
+    let impossible = Config {
+      width:     3,
+      height:    3,
+      density:   0.0,
+      seed:      0,
+      guarantee: Guarantee::LoopPositions(9),
+    }
+    .generate();
+    assert!(matches!(
+      impossible,
+      Err(generate::GenerateError::LoopPositions { wanted: 9, .. })
+    ));
+
+    let config = |width, density| Config {
+      width,
+      height: 1,
+      density,
+      seed: 0,
+      guarantee: Guarantee::Nothing,
+    };
+    assert_eq!(
+      config(1, 0.1).generate().err(),
+      Some(generate::GenerateError::TooSmall)
+    );
+    assert_eq!(
+      config(4, 1.5).generate().err(),
+      Some(generate::GenerateError::Density(1.5))
+    );
+    assert!(config(4, f64::NAN).generate().is_err());
+    assert_eq!(
+      generate::GenerateError::LoopPositions {
+        wanted:  9,
+        closest: 4,
+      }
+      .to_string(),
+      "wanted 9 loop positions, but got no closer than 4 in 1000 maps"
+    );
+  }
+
+  #[test]
+  fn topologies() {
+    let input = Input::from_string(MINI_INPUT.to_string());
+    let with = |topology| MovementRules {
+      topology,
+      ..MovementRules::default()
+    };
+
+    for topology in [Topology::Torus, Topology::Walled] {
+      let sim =
+        GuardSim::with_rules(&input.grid, input.start(), with(topology));
+      assert_eq!(sim.run(), Outcome::Loop, "{topology:?}");
+    }
+    assert_eq!(input.part_1_with(with(Topology::Bounded)), 41);
+    assert_eq!(input.part_1_with(with(Topology::Torus)), 52);
+    assert_eq!(input.part_1_with(with(Topology::Walled)), 48);
+    // the guard always loops, so every free cell would make it loop. These
+    // are the obstacles that change its loop, which are all on the cells it
+    // visits
+    assert_eq!(input.part_2_stepwise_with(with(Topology::Torus)), 91);
+    assert_eq!(input.part_2_loop_changes_with(with(Topology::Torus)), 47);
+    assert_eq!(input.part_2_loop_changes_with(with(Topology::Walled)), 37);
+
+    // a guard walking along an empty row comes back round to where it began
+    let input = Input::from_string("..>..\n".to_string());
+    let mut sim =
+      GuardSim::with_rules(&input.grid, input.start(), with(Topology::Torus));
+    let xs: Vec<_> = sim.by_ref().map(|s| s.pos.0).collect();
+    assert_eq!(xs, [2, 3, 4, 0, 1]);
+    assert_eq!(sim.outcome(), Some(Outcome::Loop));
+
+    // walled in, it bounces between the walls instead
+    let mut sim =
+      GuardSim::with_rules(&input.grid, input.start(), with(Topology::Walled));
+    assert_eq!(sim.by_ref().count(), 12);
+    assert_eq!(sim.outcome(), Some(Outcome::Loop));
+  }
+
+  #[test]
+  #[should_panic(expected = "expected exactly one guard, found 2")]
+  fn rejects_several_guards() {
+    Input::from_string(".^..\n...<\n".to_string()).part_1();
+  }
+
+  #[test]
+  fn several_guards() {
+    const GUARDS: &str = r#"
+.....
+..<..
+.....
+^....
+"#;
+    let input = Input::from_string(GUARDS.to_string());
+    let report =
+      guards::walk_guards(&input.grid, &input.guards, MovementRules::default());
+
+    assert_eq!(input.guards, [
+      GuardState {
+        pos: (2, 1),
+        dir: Direction8::Left,
+      },
+      GuardState {
+        pos: (0, 3),
+        dir: Direction8::Up,
+      },
+    ]);
+    assert_eq!(
+      report.walks.iter().map(|w| w.visited).collect::<Vec<_>>(),
+      [3, 4]
+    );
+    assert!(report.walks.iter().all(|w| w.outcome == Outcome::Exit));
+    assert_eq!(report.collisions, [guards::Collision {
+      step:   2,
+      guards: (0, 1),
+      pos:    (0, 1),
+      swap:   false,
+    }]);
+    assert_eq!(report.coverage, 6);
+
+    // facing each other in neighbouring cells, they pass through each other
+    let input = Input::from_string(".><.".to_string());
+    let report =
+      guards::walk_guards(&input.grid, &input.guards, MovementRules::default());
+    assert_eq!(report.collisions, [guards::Collision {
+      step:   1,
+      guards: (0, 1),
+      pos:    (2, 0),
+      swap:   true,
+    }]);
+  }
+
+  #[test]
+  fn jump_table_stops() {
+    let input = Input::from_string(MINI_INPUT.to_string());
+    let table = JumpTable::new(&input.grid);
+    assert_eq!(table.stop((4, 6), Direction4::Up, None), Some((4, 1)));
+    assert_eq!(table.stop((4, 1), Direction4::Right, None), Some((8, 1)));
+    assert_eq!(table.stop((7, 7), Direction4::Down, None), None);
+    // an extra obstacle in the way cuts the run short, one past the stop
+    // doesn't
+    assert_eq!(
+      table.stop((4, 6), Direction4::Up, Some((4, 3))),
+      Some((4, 4))
+    );
+    assert_eq!(
+      table.stop((4, 6), Direction4::Up, Some((4, 0))),
+      Some((4, 1))
+    );
+    assert_eq!(
+      table.stop((7, 7), Direction4::Down, Some((7, 9))),
+      Some((7, 8))
+    );
+
+    // the table has no runs for the diagonals, so a guard facing one has to
+    // be stepped through instead
+    let diagonal = GuardState {
+      pos: (4, 6),
+      dir: Direction8::UpRight,
+    };
+    assert_eq!(GuardState::<Direction4>::try_from(diagonal), Err(()));
+    assert_eq!(
+      GuardState::<Direction4>::try_from(input.start()),
+      Ok(GuardState {
+        pos: (4, 6),
+        dir: Direction4::Up,
+      })
+    );
+  }
+
+  #[test]
+  fn guard_sim_steps() {
+    let input = Input::from_string(MINI_INPUT.to_string());
+    let states: Vec<_> = GuardSim::new(&input.grid, input.start())
+      .skip(4)
+      .take(3)
+      .collect();
+    // the guard walks up to the obstacle, then turns in place
+    assert_eq!(states, vec![
+      GuardState {
+        pos: (4, 2),
+        dir: Direction8::Up,
+      },
+      GuardState {
+        pos: (4, 1),
+        dir: Direction8::Up,
+      },
+      GuardState {
+        pos: (4, 1),
+        dir: Direction8::Right,
+      },
+    ]);
+
+    let mut grid = input.grid.clone();
+    grid.set(3, 6, true);
+    assert_eq!(GuardSim::new(&grid, input.start()).run(), Outcome::Loop);
+  }
 }
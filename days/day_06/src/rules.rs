@@ -0,0 +1,99 @@
+use std::str::FromStr;
+
+use direction::Direction8;
+
+/// Which way the guard turns when it reaches an obstacle.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Turn {
+  #[default]
+  Right,
+  Left,
+  Around,
+}
+
+/// Whether the guard can move diagonally. With eight directions, turning
+/// left or right is by 45 degrees rather than 90.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Directions {
+  #[default]
+  Four,
+  Eight,
+}
+
+/// What the guard does when it reaches an obstacle.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AtObstacle {
+  #[default]
+  Turn,
+  /// Stop walking, which ends the walk.
+  Stop,
+}
+
+/// What happens when the guard walks off an edge of the grid.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Topology {
+  /// The guard leaves, which ends the walk.
+  #[default]
+  Bounded,
+  /// The guard comes back in on the opposite edge. There is then no way for
+  /// the walk to end except by looping (or stopping).
+  Torus,
+  /// The edge is a wall, and the guard treats it like an obstacle. As on a
+  /// torus, the walk can't end by leaving.
+  Walled,
+}
+
+/// How the guard moves. The default is the puzzle's: four directions, a
+/// right turn at every obstacle, and leaving at the edge of the grid.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MovementRules {
+  pub turn:        Turn,
+  pub directions:  Directions,
+  pub at_obstacle: AtObstacle,
+  pub topology:    Topology,
+}
+
+impl MovementRules {
+  /// The direction the guard faces after turning away from an obstacle.
+  pub fn turn(&self, dir: Direction8) -> Direction8 {
+    let quarter = match self.directions {
+      Directions::Four => 2,
+      Directions::Eight => 1,
+    };
+    match self.turn {
+      Turn::Right => dir.rotate(quarter),
+      Turn::Left => dir.rotate(-quarter),
+      Turn::Around => dir.rotate(4),
+    }
+  }
+}
+
+impl FromStr for Turn {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "right" => Ok(Turn::Right),
+      "left" => Ok(Turn::Left),
+      "around" => Ok(Turn::Around),
+      s => Err(format!(
+        "unknown turn {s:?}, expected right, left or around"
+      )),
+    }
+  }
+}
+
+impl FromStr for Topology {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "bounded" => Ok(Topology::Bounded),
+      "torus" => Ok(Topology::Torus),
+      "walled" => Ok(Topology::Walled),
+      s => Err(format!(
+        "unknown topology {s:?}, expected bounded, torus or walled"
+      )),
+    }
+  }
+}
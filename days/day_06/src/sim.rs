@@ -0,0 +1,161 @@
+use direction::{Direction4, Direction8};
+
+use crate::{
+  grid::Grid,
+  rules::{AtObstacle, MovementRules, Topology},
+};
+
+/// Where the guard is and which way it is facing. Only the eight-way rules
+/// need the diagonals, so code that can't handle them, like the jump table,
+/// takes a `GuardState<Direction4>` instead.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct GuardState<D = Direction8> {
+  pub pos: (usize, usize),
+  pub dir: D,
+}
+
+impl TryFrom<GuardState> for GuardState<Direction4> {
+  /// The guard is facing diagonally.
+  type Error = ();
+
+  fn try_from(state: GuardState) -> Result<Self, Self::Error> {
+    Ok(GuardState {
+      pos: state.pos,
+      dir: state.dir.try_into()?,
+    })
+  }
+}
+
+/// How a guard's walk ended.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Outcome {
+  /// The guard walked off the edge of the grid.
+  Exit,
+  /// The guard came back to a state it had already been in, so it will
+  /// walk the same loop forever.
+  Loop,
+  /// The guard reached an obstacle and stopped, under
+  /// [`AtObstacle::Stop`].
+  Stop,
+}
+
+/// Steps a guard through the grid. Walking forward and turning in place are
+/// both steps, so every state is yielded exactly once, starting
+/// with the initial one. Once the iterator is exhausted, [`GuardSim::outcome`]
+/// says why.
+pub struct GuardSim<'a> {
+  grid:     &'a Grid,
+  rules:    MovementRules,
+  next:     Option<GuardState>,
+  /// Which directions the guard has faced in each cell.
+  visited:  Vec<[bool; 8]>,
+  outcome:  Option<Outcome>,
+  /// The state that was reached a second time, if the walk looped.
+  repeated: Option<GuardState>,
+}
+
+impl<'a> GuardSim<'a> {
+  pub fn new(grid: &'a Grid, start: GuardState) -> Self {
+    Self::with_rules(grid, start, MovementRules::default())
+  }
+
+  pub fn with_rules(
+    grid: &'a Grid,
+    start: GuardState,
+    rules: MovementRules,
+  ) -> Self {
+    Self {
+      grid,
+      rules,
+      next: Some(start),
+      visited: vec![[false; 8]; grid.width() * grid.height()],
+      outcome: None,
+      repeated: None,
+    }
+  }
+
+  /// How the walk ended, once the iterator has been exhausted.
+  pub fn outcome(&self) -> Option<Outcome> { self.outcome }
+
+  /// The first state of the loop, once the iterator has been exhausted by the
+  /// guard coming back to it.
+  pub fn loop_start(&self) -> Option<GuardState> { self.repeated }
+
+  /// Runs the walk to the end and returns how it ended.
+  pub fn run(mut self) -> Outcome {
+    self.by_ref().for_each(drop);
+    self.outcome.expect("walk ended without an outcome")
+  }
+}
+
+/// The cell a guard in `state` walks into next, or bumps into if it is
+/// blocked. This is `None` if the guard is at the edge of the grid, facing
+/// out, and the edges don't wrap.
+pub fn target(
+  grid: &Grid,
+  rules: MovementRules,
+  state: GuardState,
+) -> Option<(usize, usize)> {
+  let (dx, dy) = state.dir.vector();
+  let (width, height) = (grid.width() as isize, grid.height() as isize);
+  let (x, y) = (state.pos.0 as isize + dx, state.pos.1 as isize + dy);
+
+  if x >= 0 && x < width && y >= 0 && y < height {
+    Some((x as usize, y as usize))
+  } else if rules.topology == Topology::Torus {
+    Some((x.rem_euclid(width) as usize, y.rem_euclid(height) as usize))
+  } else {
+    None
+  }
+}
+
+/// The state after `state`, or how the walk ends if there is none.
+pub fn step(
+  grid: &Grid,
+  rules: MovementRules,
+  state: GuardState,
+) -> Result<GuardState, Outcome> {
+  let target = target(grid, rules, state);
+  let blocked = match (target, rules.topology) {
+    (Some((x, y)), _) => grid.get(x, y),
+    (None, Topology::Walled) => true,
+    (None, _) => return Err(Outcome::Exit),
+  };
+
+  match (target, blocked) {
+    (Some(pos), false) => Ok(GuardState {
+      pos,
+      dir: state.dir,
+    }),
+    _ => match rules.at_obstacle {
+      AtObstacle::Turn => Ok(GuardState {
+        pos: state.pos,
+        dir: rules.turn(state.dir),
+      }),
+      AtObstacle::Stop => Err(Outcome::Stop),
+    },
+  }
+}
+
+impl Iterator for GuardSim<'_> {
+  type Item = GuardState;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    let state = self.next.take()?;
+
+    let seen = &mut self.visited[state.pos.1 * self.grid.width() + state.pos.0]
+      [state.dir.as_usize()];
+    if *seen {
+      self.outcome = Some(Outcome::Loop);
+      self.repeated = Some(state);
+      return None;
+    }
+    *seen = true;
+
+    match step(self.grid, self.rules, state) {
+      Ok(next) => self.next = Some(next),
+      Err(outcome) => self.outcome = Some(outcome),
+    }
+    Some(state)
+  }
+}